@@ -0,0 +1,1052 @@
+use crate::DecodeError;
+use chardetng::EncodingDetector;
+use encoding_rs::{DecoderResult, Encoding, UTF_8, UTF_16BE, UTF_16LE};
+use rayon::prelude::*;
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{Condvar, Mutex, PoisonError},
+};
+
+pub mod priors;
+
+pub use priors::ExtensionPriors;
+
+/// 检测出的文本编码
+///
+/// `encoding_rs` 不支持 UTF-32，因此单独列出，其余编码统一用 [`Encoding`] 表示。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf32Le,
+    Utf32Be,
+    Other(&'static Encoding),
+}
+
+impl TextEncoding {
+    /// 编码的规范名称，如 `"UTF-8"`、`"GBK"`、`"UTF-32LE"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextEncoding::Utf32Le => "UTF-32LE",
+            TextEncoding::Utf32Be => "UTF-32BE",
+            TextEncoding::Other(encoding) => encoding.name(),
+        }
+    }
+
+    /// 该编码的 BOM 字节序列
+    fn bom(&self) -> &'static [u8] {
+        match self {
+            TextEncoding::Utf32Le => &[0xFF, 0xFE, 0x00, 0x00],
+            TextEncoding::Utf32Be => &[0x00, 0x00, 0xFE, 0xFF],
+            TextEncoding::Other(e) if *e == UTF_8 => &[0xEF, 0xBB, 0xBF],
+            TextEncoding::Other(e) if *e == UTF_16LE => &[0xFF, 0xFE],
+            TextEncoding::Other(e) if *e == UTF_16BE => &[0xFE, 0xFF],
+            TextEncoding::Other(_) => &[],
+        }
+    }
+}
+
+/// 解码结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    /// 转换后的 UTF-8 文本（不含 BOM）
+    pub text: String,
+    /// 实际使用的编码
+    pub encoding: TextEncoding,
+    /// 宽松模式下被替换为 U+FFFD 的非法序列个数，严格模式下恒为 0
+    pub replacements: usize,
+}
+
+impl Decoded {
+    /// 把 CRLF、单独的 CR 与 LF 统一为 `style`，返回文本是否因此改变
+    pub fn normalize_newlines(&mut self, style: NewlineStyle) -> bool {
+        match normalize_newlines(&self.text, style) {
+            Some(text) => {
+                self.text = text;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 统一换行符时使用的目标格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewlineStyle {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl NewlineStyle {
+    fn as_str(&self) -> &'static str {
+        match self {
+            NewlineStyle::Lf => "\n",
+            NewlineStyle::CrLf => "\r\n",
+        }
+    }
+}
+
+/// 遇到非法字节序列时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecodeMode {
+    /// 替换为 U+FFFD 并计数
+    #[default]
+    Lossy,
+    /// 在第一个非法字节处返回 [`DecodeError::InvalidSequence`]
+    Strict,
+}
+
+/// 编码检测结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectionResult {
+    /// 检测出的编码
+    pub encoding: TextEncoding,
+    /// 置信度，取值 `0.0..=1.0`；有 BOM 时为 1.0，短文本会被压低
+    pub confidence: f32,
+    /// 是否由 BOM 确定
+    pub had_bom: bool,
+}
+
+/// 样本达到该长度后不再因长度降低置信度
+const CONFIDENT_LEN: usize = 256;
+
+/// 检测字节序列的编码
+///
+/// 依次尝试：BOM；无 BOM 时按空字节分布推断 UTF-32/UTF-16（并校验码点与代理对）；
+/// 合法 UTF-8；最后交给 `chardetng` 判断 GBK、Shift_JIS 等传统编码。
+///
+/// 除 BOM 外，置信度都会随样本长度衰减：几十字节的文件很难可靠区分传统编码，
+/// 调用方可在置信度较低时改用自己指定的编码。
+pub fn detect_encoding(bytes: &[u8]) -> DetectionResult {
+    if let Some(encoding) = sniff_bom(bytes) {
+        return DetectionResult {
+            encoding,
+            confidence: 1.0,
+            had_bom: true,
+        };
+    }
+
+    let (encoding, score) = if let Some((encoding, share)) = guess_wide(bytes) {
+        // share 越接近 1，空字节分布越整齐
+        (encoding, 0.6 + 0.4 * share)
+    } else if std::str::from_utf8(bytes).is_ok() {
+        // 纯 ASCII 在所有 ASCII 兼容编码下结果相同，按 UTF-8 解码不会出错
+        let score = if bytes.is_ascii() { 1.0 } else { 0.95 };
+        (TextEncoding::Other(UTF_8), score)
+    } else {
+        let mut detector = EncodingDetector::new();
+        detector.feed(bytes, true);
+        let (encoding, assured) = detector.guess_assess(None, true);
+        (
+            TextEncoding::Other(encoding),
+            if assured { 0.8 } else { 0.4 },
+        )
+    };
+
+    let length_factor = (bytes.len().min(CONFIDENT_LEN) as f32) / CONFIDENT_LEN as f32;
+    DetectionResult {
+        encoding,
+        confidence: score * (0.5 + 0.5 * length_factor),
+        had_bom: false,
+    }
+}
+
+/// 自动检测编码并解码为 UTF-8，无法解码的序列替换为 U+FFFD
+pub fn decode(bytes: &[u8]) -> Decoded {
+    decode_as(bytes, detect_encoding(bytes).encoding)
+}
+
+/// 检测置信度低于该值时，[`decode_with_hint`] 改用调用方给出的编码
+pub const HINT_THRESHOLD: f32 = 0.6;
+
+/// [`decode_with_hint`] 的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct HintedDecoded {
+    /// 解码结果，`encoding` 为实际使用的编码
+    pub decoded: Decoded,
+    /// 原始检测结果
+    pub detection: DetectionResult,
+    /// 是否因置信度不足而改用了提示编码
+    pub hint_applied: bool,
+}
+
+/// 带编码提示的解码
+///
+/// 检测置信度低于 [`HINT_THRESHOLD`] 时使用 `preferred`，否则仍以检测结果为准。
+/// 有 BOM 时置信度为 1.0，提示不会生效。
+pub fn decode_with_hint(bytes: &[u8], preferred: Option<&'static Encoding>) -> HintedDecoded {
+    let detection = detect_encoding(bytes);
+    let hint = preferred
+        .filter(|_| detection.confidence < HINT_THRESHOLD)
+        .map(TextEncoding::Other);
+    let encoding = hint.unwrap_or(detection.encoding);
+
+    HintedDecoded {
+        decoded: decode_as(bytes, encoding),
+        detection,
+        hint_applied: hint.is_some_and(|hint| hint != detection.encoding),
+    }
+}
+
+/// [`decode_per_line`] 的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineDecoded {
+    /// 各行解码后拼接成的 UTF-8 文本，保留原有的换行符
+    pub text: String,
+    /// 每一行使用的编码，下标为行号（从 0 开始）
+    pub line_encodings: Vec<TextEncoding>,
+    /// 被替换为 U+FFFD 的非法序列总数
+    pub replacements: usize,
+}
+
+/// 逐行检测编码并宽松解码，用于同一文件中混有多种编码的语料（如抓取拼接的数据）
+///
+/// 有 BOM 或按空字节分布判断为 UTF-16/UTF-32 时整个文件只有一种编码，按整体解码。
+/// 否则按 `\n` 分行：合法 UTF-8（含纯 ASCII）的行按 UTF-8 解码，其余行交给 `chardetng`；
+/// 单行太短无法确定时，改用所有非 UTF-8 行合在一起检测出的编码。
+pub fn decode_per_line(bytes: &[u8]) -> LineDecoded {
+    if sniff_bom(bytes).is_some() || guess_wide(bytes).is_some() {
+        let decoded = decode(bytes);
+        let lines = decoded.text.split_inclusive('\n').count();
+        return LineDecoded {
+            line_encodings: vec![decoded.encoding; lines],
+            text: decoded.text,
+            replacements: decoded.replacements,
+        };
+    }
+
+    let lines: Vec<&[u8]> = bytes.split_inclusive(|&b| b == b'\n').collect();
+    let is_utf8 = |line: &[u8]| std::str::from_utf8(line).is_ok();
+    let mut fallback = None;
+    let mut result = LineDecoded {
+        text: String::with_capacity(bytes.len()),
+        line_encodings: Vec::with_capacity(lines.len()),
+        replacements: 0,
+    };
+    for line in &lines {
+        let encoding = if is_utf8(line) {
+            UTF_8
+        } else {
+            let mut detector = EncodingDetector::new();
+            detector.feed(line, true);
+            match detector.guess_assess(None, true) {
+                (encoding, true) => encoding,
+                _ => *fallback.get_or_insert_with(|| {
+                    let mut detector = EncodingDetector::new();
+                    for line in lines.iter().filter(|line| !is_utf8(line)) {
+                        detector.feed(line, false);
+                    }
+                    detector.feed(b"", true);
+                    detector.guess(None, true)
+                }),
+            }
+        };
+        let decoded = decode_as(line, TextEncoding::Other(encoding));
+        result.text.push_str(&decoded.text);
+        result.replacements += decoded.replacements;
+        result.line_encodings.push(decoded.encoding);
+    }
+    result
+}
+
+/// 按指定编码宽松解码（存在匹配的 BOM 时会去掉）
+pub fn decode_as(bytes: &[u8], encoding: TextEncoding) -> Decoded {
+    decode_as_with_mode(bytes, encoding, DecodeMode::Lossy).expect("宽松模式不会返回错误")
+}
+
+/// 自动检测编码并按 `mode` 解码
+pub fn decode_with_mode(bytes: &[u8], mode: DecodeMode) -> Result<Decoded, DecodeError> {
+    decode_as_with_mode(bytes, detect_encoding(bytes).encoding, mode)
+}
+
+/// 按指定编码和模式解码
+///
+/// 严格模式下错误中的偏移量相对于整个输入（包含 BOM）。
+pub fn decode_as_with_mode(
+    bytes: &[u8],
+    encoding: TextEncoding,
+    mode: DecodeMode,
+) -> Result<Decoded, DecodeError> {
+    let bom_len = if bytes.starts_with(encoding.bom()) {
+        encoding.bom().len()
+    } else {
+        0
+    };
+    let body = &bytes[bom_len..];
+    let strict = mode == DecodeMode::Strict;
+    let decoded = match encoding {
+        TextEncoding::Utf32Le => decode_utf32(body, u32::from_le_bytes, strict),
+        TextEncoding::Utf32Be => decode_utf32(body, u32::from_be_bytes, strict),
+        TextEncoding::Other(encoding) => transcode(body, encoding, strict),
+    };
+
+    match decoded {
+        Ok((text, replacements)) => Ok(Decoded {
+            text,
+            encoding,
+            replacements,
+        }),
+        Err(offset) => Err(DecodeError::InvalidSequence {
+            encoding: encoding.name(),
+            offset: bom_len + offset,
+        }),
+    }
+}
+
+/// [`classify`] 的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Text,
+    /// 图片、可执行文件等，不应做编码检测
+    Binary,
+    Empty,
+}
+
+/// 分类时检查的前缀长度
+const CLASSIFY_SAMPLE_LEN: usize = 8 * 1024;
+
+/// 控制字符占样本的比例超过该值时视为二进制
+const CONTROL_CHAR_LIMIT: f32 = 0.1;
+
+/// 根据文件开头的一段内容判断它是文本还是二进制
+///
+/// 见 [`classify_bytes`]；只读取前 8KB，适合在做编码检测之前快速过滤。
+pub fn classify<P: AsRef<Path>>(path: P) -> io::Result<FileKind> {
+    // 多读一个字节，让 `classify_bytes` 知道样本是否被截断
+    let mut sample = Vec::with_capacity(CLASSIFY_SAMPLE_LEN + 1);
+    File::open(path)?
+        .take(CLASSIFY_SAMPLE_LEN as u64 + 1)
+        .read_to_end(&mut sample)?;
+    Ok(classify_bytes(&sample))
+}
+
+/// 判断字节序列是文本还是二进制
+///
+/// 有 BOM 或空字节分布符合 UTF-16/UTF-32 的视为文本；否则出现空字节，或除制表、换行、
+/// 回车、换页、ESC 以外的 C0 控制字符超过 10% 时视为二进制。只检查前 8KB。
+pub fn classify_bytes(bytes: &[u8]) -> FileKind {
+    if bytes.is_empty() {
+        return FileKind::Empty;
+    }
+    let sample = &bytes[..bytes.len().min(CLASSIFY_SAMPLE_LEN)];
+    let wide = if bytes.len() > CLASSIFY_SAMPLE_LEN {
+        guess_wide_truncated(sample)
+    } else {
+        guess_wide(sample)
+    };
+    if sniff_bom(sample).is_some() || wide.is_some() {
+        return FileKind::Text;
+    }
+    if sample.contains(&0) {
+        return FileKind::Binary;
+    }
+    let control = sample
+        .iter()
+        .filter(|&&b| (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B)) || b == 0x7F)
+        .count();
+    if control as f32 > sample.len() as f32 * CONTROL_CHAR_LIMIT {
+        FileKind::Binary
+    } else {
+        FileKind::Text
+    }
+}
+
+/// 读取并解码单个文件
+pub fn decode_file<P: AsRef<Path>>(path: P, mode: DecodeMode) -> Result<Decoded, DecodeError> {
+    let bytes = fs::read(path)?;
+    decode_with_mode(&bytes, mode)
+}
+
+/// 读取并解码单个文件，以扩展名对应的先验编码作为 [`decode_with_hint`] 的提示
+///
+/// 适合检测不可靠的短文件：内容足以确定编码时先验不起作用，结果中的
+/// `hint_applied` 表示是否采用了先验。
+pub fn decode_file_with_priors<P: AsRef<Path>>(
+    path: P,
+    priors: &ExtensionPriors,
+) -> Result<HintedDecoded, DecodeError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)?;
+    Ok(decode_with_hint(&bytes, priors.for_path(path)))
+}
+
+/// 目录中单个文件的解码结果
+pub type FileDecodeResult = (PathBuf, Result<Decoded, DecodeError>);
+
+/// 解码目录下的所有文件（不递归），按路径排序返回每个文件各自的结果
+///
+/// 被 [`classify`] 判为二进制的文件不做编码检测，结果为 [`DecodeError::BinaryContent`]。
+pub fn decode_directory<P: AsRef<Path>>(
+    dir: P,
+    mode: DecodeMode,
+) -> Result<Vec<FileDecodeResult>, DecodeError> {
+    Ok(directory_files(dir.as_ref())?
+        .into_iter()
+        .map(|path| {
+            let decoded = decode_listed(&path, mode);
+            (path, decoded)
+        })
+        .collect())
+}
+
+/// [`decode_directory_parallel`] 的结果
+#[derive(Debug)]
+pub struct ParallelDecode {
+    /// 按路径排序的每个文件的结果，与 [`decode_directory`] 相同
+    pub results: Vec<FileDecodeResult>,
+    /// 解码过程中同时读入内存的文件字节数的峰值
+    pub peak_in_flight_bytes: u64,
+}
+
+/// 与 [`decode_directory`] 相同，但用 `concurrency` 个线程（0 表示按 CPU 核数）并行解码，
+/// 并限制同时读入内存的文件总字节数不超过 `max_in_flight_bytes`
+///
+/// 按文件大小计入限额，额度不足时线程等待其他文件解码完成；单个文件超过限额时
+/// 等其他文件全部完成后单独解码，因此峰值不会超过 `max(限额, 最大文件)`。
+/// 限额只约束解码中的原始数据，已完成的解码结果仍会保留到返回为止。
+pub fn decode_directory_parallel<P: AsRef<Path>>(
+    dir: P,
+    mode: DecodeMode,
+    concurrency: usize,
+    max_in_flight_bytes: u64,
+) -> Result<ParallelDecode, DecodeError> {
+    let paths = directory_files(dir.as_ref())?;
+    let budget = MemoryBudget::new(max_in_flight_bytes);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(concurrency)
+        .build()
+        .map_err(io::Error::other)?;
+    let results = pool.install(|| {
+        paths
+            .into_par_iter()
+            .map(|path| {
+                let size = fs::metadata(&path).map_or(0, |meta| meta.len());
+                budget.acquire(size);
+                let decoded = decode_listed(&path, mode);
+                budget.release(size);
+                (path, decoded)
+            })
+            .collect()
+    });
+    Ok(ParallelDecode {
+        results,
+        peak_in_flight_bytes: budget.peak(),
+    })
+}
+
+/// 目录下的文件（不递归），按路径排序
+fn directory_files(dir: &Path) -> Result<Vec<PathBuf>, DecodeError> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// 解码目录中的一个文件，二进制文件返回 [`DecodeError::BinaryContent`]
+fn decode_listed(path: &Path, mode: DecodeMode) -> Result<Decoded, DecodeError> {
+    match classify(path) {
+        Ok(FileKind::Binary) => Err(DecodeError::BinaryContent {
+            path: path.to_path_buf(),
+        }),
+        Ok(_) => decode_file(path, mode),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 按字节计的信号量：`(已占用, 峰值)`
+struct MemoryBudget {
+    limit: u64,
+    state: Mutex<(u64, u64)>,
+    released: Condvar,
+}
+
+impl MemoryBudget {
+    fn new(limit: u64) -> Self {
+        MemoryBudget {
+            limit,
+            state: Mutex::new((0, 0)),
+            released: Condvar::new(),
+        }
+    }
+
+    /// 等待额度足够（或没有其他占用）后占用 `size` 字节
+    fn acquire(&self, size: u64) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        while state.0 > 0 && state.0 + size > self.limit {
+            state = self
+                .released
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        state.0 += size;
+        state.1 = state.1.max(state.0);
+    }
+
+    fn release(&self, size: u64) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.0 -= size;
+        self.released.notify_all();
+    }
+
+    fn peak(&self) -> u64 {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).1
+    }
+}
+
+/// 统一换行符，文本已经符合 `style` 时返回 `None`
+fn normalize_newlines(text: &str, style: NewlineStyle) -> Option<String> {
+    let target = style.as_str();
+    let mut out = String::with_capacity(text.len());
+    let mut changed = false;
+    let mut rest = text;
+    while let Some(pos) = rest.find(['\r', '\n']) {
+        out.push_str(&rest[..pos]);
+        let newline_len = if rest[pos..].starts_with("\r\n") {
+            2
+        } else {
+            1
+        };
+        changed |= &rest[pos..pos + newline_len] != target;
+        out.push_str(target);
+        rest = &rest[pos + newline_len..];
+    }
+    out.push_str(rest);
+    changed.then_some(out)
+}
+
+fn sniff_bom(bytes: &[u8]) -> Option<TextEncoding> {
+    // UTF-32LE 的 BOM 以 UTF-16LE 的 BOM 开头，必须先判断
+    match bytes {
+        [0xFF, 0xFE, 0x00, 0x00, ..] => Some(TextEncoding::Utf32Le),
+        [0x00, 0x00, 0xFE, 0xFF, ..] => Some(TextEncoding::Utf32Be),
+        [0xEF, 0xBB, 0xBF, ..] => Some(TextEncoding::Other(UTF_8)),
+        [0xFF, 0xFE, ..] => Some(TextEncoding::Other(UTF_16LE)),
+        [0xFE, 0xFF, ..] => Some(TextEncoding::Other(UTF_16BE)),
+        _ => None,
+    }
+}
+
+/// 无 BOM 时根据空字节的位置分布推断 UTF-32/UTF-16
+///
+/// 以 ASCII/拉丁字符为主的 UTF-16LE 文本在奇数位上几乎全是 0，偶数位上几乎没有 0；
+/// UTF-32 则每 4 字节中有 2~3 个 0。候选编码还必须能无错解码，否则放弃。
+///
+/// 返回编码以及符合该分布的码元比例。
+fn guess_wide(bytes: &[u8]) -> Option<(TextEncoding, f32)> {
+    if bytes.len() < 4 {
+        return None;
+    }
+
+    if bytes.len().is_multiple_of(4) {
+        let units = bytes.len() / 4;
+        let le = bytes
+            .chunks_exact(4)
+            .filter(|c| c[2] == 0 && c[3] == 0 && c[0] != 0)
+            .count();
+        let be = bytes
+            .chunks_exact(4)
+            .filter(|c| c[0] == 0 && c[1] == 0 && c[3] != 0)
+            .count();
+        if le * 10 >= units * 9 && valid_utf32(bytes, u32::from_le_bytes) {
+            return Some((TextEncoding::Utf32Le, le as f32 / units as f32));
+        }
+        if be * 10 >= units * 9 && valid_utf32(bytes, u32::from_be_bytes) {
+            return Some((TextEncoding::Utf32Be, be as f32 / units as f32));
+        }
+    }
+
+    if bytes.len().is_multiple_of(2) {
+        let pairs = bytes.len() / 2;
+        let even_zero = bytes.iter().step_by(2).filter(|&&b| b == 0).count();
+        let odd_zero = bytes.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+        // 至少 40% 的码元在一侧有空字节，另一侧的空字节不超过其四分之一
+        // （代理对或 U+xx00 之类的字符会在另一侧产生少量空字节）
+        if odd_zero * 10 >= pairs * 4 && even_zero * 4 <= odd_zero && valid_utf16(bytes, true) {
+            return Some((
+                TextEncoding::Other(UTF_16LE),
+                odd_zero as f32 / pairs as f32,
+            ));
+        }
+        if even_zero * 10 >= pairs * 4 && odd_zero * 4 <= even_zero && valid_utf16(bytes, false) {
+            return Some((
+                TextEncoding::Other(UTF_16BE),
+                even_zero as f32 / pairs as f32,
+            ));
+        }
+    }
+    None
+}
+
+/// 对截断的样本做 [`guess_wide`]
+///
+/// 截断处可能切开了 UTF-16 码元或代理对：先去掉末尾的奇数字节，不成立时再去掉最后一个码元
+/// （可能是孤立的高代理）后重试。样本是合法文本的前缀，去掉末尾不会让二进制被误判为文本。
+fn guess_wide_truncated(sample: &[u8]) -> Option<(TextEncoding, f32)> {
+    let even = &sample[..sample.len() & !1];
+    guess_wide(even).or_else(|| {
+        let trimmed = even.len().checked_sub(2)?;
+        guess_wide(&even[..trimmed])
+    })
+}
+
+/// UTF-16 码元序列是否合法（高代理后必须紧跟低代理，不允许孤立的低代理）
+fn valid_utf16(bytes: &[u8], little_endian: bool) -> bool {
+    let units = bytes.chunks_exact(2).map(|c| {
+        if little_endian {
+            u16::from_le_bytes([c[0], c[1]])
+        } else {
+            u16::from_be_bytes([c[0], c[1]])
+        }
+    });
+    char::decode_utf16(units).all(|c| c.is_ok())
+}
+
+fn valid_utf32(bytes: &[u8], to_u32: fn([u8; 4]) -> u32) -> bool {
+    bytes
+        .chunks_exact(4)
+        .all(|c| char::from_u32(to_u32([c[0], c[1], c[2], c[3]])).is_some())
+}
+
+/// 用 `encoding_rs` 解码，返回文本与替换次数；严格模式下返回首个非法字节的偏移
+fn transcode(
+    bytes: &[u8],
+    encoding: &'static Encoding,
+    strict: bool,
+) -> Result<(String, usize), usize> {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut text = String::new();
+    let mut read = 0;
+    let mut replacements = 0;
+    loop {
+        let rest = &bytes[read..];
+        if let Some(needed) = decoder.max_utf8_buffer_length_without_replacement(rest.len()) {
+            text.reserve(needed);
+        }
+        let (result, consumed) =
+            decoder.decode_to_string_without_replacement(rest, &mut text, true);
+        read += consumed;
+        match result {
+            DecoderResult::InputEmpty => return Ok((text, replacements)),
+            DecoderResult::OutputFull => text.reserve(4),
+            DecoderResult::Malformed(bad, after) => {
+                if strict {
+                    return Err(read - after as usize - bad as usize);
+                }
+                text.push(char::REPLACEMENT_CHARACTER);
+                replacements += 1;
+            }
+        }
+    }
+}
+
+/// 手动解码 UTF-32，非法码点与末尾残缺的字节按模式替换为 U+FFFD 或报错
+fn decode_utf32(
+    bytes: &[u8],
+    to_u32: fn([u8; 4]) -> u32,
+    strict: bool,
+) -> Result<(String, usize), usize> {
+    let chunks = bytes.chunks_exact(4);
+    let trailing = !chunks.remainder().is_empty();
+    let mut text = String::with_capacity(bytes.len());
+    let mut replacements = 0;
+    for (i, c) in chunks.enumerate() {
+        match char::from_u32(to_u32([c[0], c[1], c[2], c[3]])) {
+            Some(ch) => text.push(ch),
+            None if strict => return Err(i * 4),
+            None => {
+                text.push(char::REPLACEMENT_CHARACTER);
+                replacements += 1;
+            }
+        }
+    }
+    if trailing {
+        if strict {
+            return Err(bytes.len() / 4 * 4);
+        }
+        text.push(char::REPLACEMENT_CHARACTER);
+        replacements += 1;
+    }
+    Ok((text, replacements))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::GBK;
+
+    fn utf16(text: &str, little_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|u| {
+                if little_endian {
+                    u.to_le_bytes()
+                } else {
+                    u.to_be_bytes()
+                }
+            })
+            .collect()
+    }
+
+    fn utf32(text: &str, little_endian: bool) -> Vec<u8> {
+        text.chars()
+            .flat_map(|c| {
+                if little_endian {
+                    (c as u32).to_le_bytes()
+                } else {
+                    (c as u32).to_be_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_utf16_with_bom() {
+        let text = "Hello, 世界 😀";
+        for (le, bom, encoding) in [
+            (true, [0xFF, 0xFE], UTF_16LE),
+            (false, [0xFE, 0xFF], UTF_16BE),
+        ] {
+            let mut bytes = bom.to_vec();
+            bytes.extend(utf16(text, le));
+            let decoded = decode(&bytes);
+            assert_eq!(decoded.encoding, TextEncoding::Other(encoding));
+            assert_eq!(decoded.text, text);
+        }
+    }
+
+    #[test]
+    fn test_utf16_without_bom() {
+        let text = "plain ascii subtitle line\r\nsecond line";
+        let decoded = decode(&utf16(text, true));
+        assert_eq!(decoded.encoding, TextEncoding::Other(UTF_16LE));
+        assert_eq!(decoded.text, text);
+
+        let decoded = decode(&utf16(text, false));
+        assert_eq!(decoded.encoding, TextEncoding::Other(UTF_16BE));
+        assert_eq!(decoded.text, text);
+    }
+
+    #[test]
+    fn test_utf16_rejects_unpaired_surrogate() {
+        // 空字节分布像 UTF-16LE，但含孤立的低代理 0xDC00，不应判为 UTF-16
+        let mut bytes = utf16("abcdefgh", true);
+        bytes.extend_from_slice(&[0x00, 0xDC]);
+        assert_ne!(
+            detect_encoding(&bytes).encoding,
+            TextEncoding::Other(UTF_16LE)
+        );
+
+        // 合法的代理对可以通过
+        let bytes = utf16("abc😀defg", true);
+        assert_eq!(
+            detect_encoding(&bytes).encoding,
+            TextEncoding::Other(UTF_16LE)
+        );
+    }
+
+    #[test]
+    fn test_utf32_variants() {
+        let text = "UTF-32 文本 😀";
+        for (le, bom, encoding) in [
+            (true, [0xFF, 0xFE, 0x00, 0x00], TextEncoding::Utf32Le),
+            (false, [0x00, 0x00, 0xFE, 0xFF], TextEncoding::Utf32Be),
+        ] {
+            let mut bytes = bom.to_vec();
+            bytes.extend(utf32(text, le));
+            let decoded = decode(&bytes);
+            assert_eq!(decoded.encoding, encoding);
+            assert_eq!(decoded.text, text);
+
+            // 无 BOM 的 ASCII 文本靠空字节分布识别
+            let decoded = decode(&utf32("just ascii", le));
+            assert_eq!(decoded.encoding, encoding);
+            assert_eq!(decoded.text, "just ascii");
+        }
+        assert_eq!(TextEncoding::Utf32Be.name(), "UTF-32BE");
+    }
+
+    #[test]
+    fn test_utf8_and_legacy() {
+        let decoded = decode("普通的 UTF-8 文本".as_bytes());
+        assert_eq!(decoded.encoding, TextEncoding::Other(UTF_8));
+
+        let mut bom = vec![0xEF, 0xBB, 0xBF];
+        bom.extend_from_slice("bom".as_bytes());
+        assert_eq!(decode(&bom).text, "bom");
+
+        let (gbk, _, _) =
+            GBK.encode("这是一段用国标编码保存的中文字幕文件内容，用来测试编码检测。");
+        let decoded = decode(&gbk);
+        assert_eq!(decoded.encoding, TextEncoding::Other(GBK));
+        assert_eq!(
+            decoded.text,
+            "这是一段用国标编码保存的中文字幕文件内容，用来测试编码检测。"
+        );
+    }
+
+    #[test]
+    fn test_detection_confidence() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(utf16("hi", true));
+        let result = detect_encoding(&bytes);
+        assert!(result.had_bom);
+        assert_eq!(result.confidence, 1.0);
+
+        // 同样的 GBK 内容，短样本的置信度低于长样本
+        let line = "这是一段用国标编码保存的中文字幕文件内容。";
+        let (short, _, _) = GBK.encode("中文");
+        let text = line.repeat(10);
+        let (long, _, _) = GBK.encode(&text);
+        let short = detect_encoding(&short);
+        let long = detect_encoding(&long);
+        assert!(!short.had_bom && !long.had_bom);
+        assert!(short.confidence < long.confidence);
+        assert!(short.confidence < 0.5);
+        assert_eq!(long.encoding, TextEncoding::Other(GBK));
+        assert!(long.confidence >= 0.8 && long.confidence <= 1.0);
+    }
+
+    #[test]
+    fn test_decode_with_hint() {
+        // 短样本检测不可靠，提示生效
+        let (short, _, _) = GBK.encode("中文");
+        let result = decode_with_hint(&short, Some(GBK));
+        assert!(result.detection.confidence < HINT_THRESHOLD);
+        assert_eq!(result.decoded.encoding, TextEncoding::Other(GBK));
+        assert_eq!(result.decoded.text, "中文");
+        assert_eq!(
+            result.hint_applied,
+            result.detection.encoding != TextEncoding::Other(GBK)
+        );
+
+        // BOM 优先于提示
+        let mut bytes = vec![0xEF, 0xBB, 0xBF];
+        bytes.extend_from_slice("中文".as_bytes());
+        let result = decode_with_hint(&bytes, Some(GBK));
+        assert!(!result.hint_applied);
+        assert_eq!(result.decoded.encoding, TextEncoding::Other(UTF_8));
+        assert_eq!(result.decoded.text, "中文");
+
+        // 无提示时与 decode 一致
+        let result = decode_with_hint(&short, None);
+        assert!(!result.hint_applied);
+        assert_eq!(result.decoded, decode(&short));
+    }
+
+    #[test]
+    fn test_decode_modes() {
+        // "ab" + 非法的 0xFF + "cd"
+        let bytes = [b'a', b'b', 0xFF, b'c', b'd'];
+        let lossy =
+            decode_as_with_mode(&bytes, TextEncoding::Other(UTF_8), DecodeMode::Lossy).unwrap();
+        assert_eq!(lossy.text, "ab\u{FFFD}cd");
+        assert_eq!(lossy.replacements, 1);
+
+        let err = decode_as_with_mode(&bytes, TextEncoding::Other(UTF_8), DecodeMode::Strict)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            DecodeError::InvalidSequence {
+                encoding: "UTF-8",
+                offset: 2
+            }
+        ));
+
+        // UTF-32 的偏移包含 BOM，非法码点 0x110000 位于第二个码元
+        let mut bytes = vec![0xFF, 0xFE, 0x00, 0x00];
+        bytes.extend_from_slice(&u32::to_le_bytes('a' as u32));
+        bytes.extend_from_slice(&u32::to_le_bytes(0x110000));
+        let err = decode_with_mode(&bytes, DecodeMode::Strict).unwrap_err();
+        assert!(matches!(
+            err,
+            DecodeError::InvalidSequence { offset: 8, .. }
+        ));
+        assert_eq!(decode(&bytes).replacements, 1);
+    }
+
+    #[test]
+    fn test_decode_directory_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "合法文本").unwrap();
+        fs::write(dir.path().join("b.txt"), [0xEF, 0xBB, 0xBF, b'x', 0xFF]).unwrap();
+
+        let results = decode_directory(dir.path(), DecodeMode::Strict).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].1.as_ref().unwrap().text, "合法文本");
+        assert!(matches!(
+            results[1].1,
+            Err(DecodeError::InvalidSequence { offset: 4, .. })
+        ));
+
+        let decoded = decode_file(dir.path().join("b.txt"), DecodeMode::Lossy).unwrap();
+        assert_eq!(decoded.text, "x\u{FFFD}");
+        assert_eq!(decoded.replacements, 1);
+    }
+
+    #[test]
+    fn test_decode_directory_parallel() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..12 {
+            fs::write(dir.path().join(format!("{:02}.txt", i)), "x".repeat(100)).unwrap();
+        }
+        fs::write(dir.path().join("big.txt"), "y".repeat(1000)).unwrap();
+        fs::write(dir.path().join("bin.dat"), b"\x89PNG\0\0\0").unwrap();
+
+        let expected = decode_directory(dir.path(), DecodeMode::Lossy).unwrap();
+        let decoded = decode_directory_parallel(dir.path(), DecodeMode::Lossy, 4, 250).unwrap();
+        assert_eq!(decoded.results.len(), expected.len());
+        for ((path, result), (expected_path, expected_result)) in
+            decoded.results.iter().zip(&expected)
+        {
+            assert_eq!(path, expected_path);
+            match (result, expected_result) {
+                (Ok(a), Ok(b)) => assert_eq!(a.text, b.text),
+                (Err(_), Err(_)) => {}
+                _ => panic!("{:?} 的结果不一致", path),
+            }
+        }
+        // 超过限额的大文件单独解码，其余时刻同时解码的小文件不超过 250 字节
+        assert_eq!(decoded.peak_in_flight_bytes, 1000);
+
+        let decoded = decode_directory_parallel(dir.path(), DecodeMode::Lossy, 4, 5000).unwrap();
+        assert!(decoded.peak_in_flight_bytes <= 5000);
+
+        // 0 表示按 CPU 核数，与 `get_file_handles_parallel` 一致
+        let decoded = decode_directory_parallel(dir.path(), DecodeMode::Lossy, 0, 5000).unwrap();
+        assert_eq!(decoded.results.len(), expected.len());
+    }
+
+    #[test]
+    fn test_decode_per_line() {
+        let gbk_line = "这一行是用简体中文国标码保存的字幕文本，用来测试逐行检测。\n";
+        let (gbk, _, _) = GBK.encode(gbk_line);
+        let mut bytes = "第一行是 UTF-8\n".as_bytes().to_vec();
+        bytes.extend_from_slice(&gbk);
+        bytes.extend_from_slice(b"plain ascii");
+
+        let decoded = decode_per_line(&bytes);
+        assert_eq!(
+            decoded.text,
+            format!("第一行是 UTF-8\n{}plain ascii", gbk_line)
+        );
+        assert_eq!(
+            decoded.line_encodings,
+            [
+                TextEncoding::Other(UTF_8),
+                TextEncoding::Other(GBK),
+                TextEncoding::Other(UTF_8)
+            ]
+        );
+        assert_eq!(decoded.replacements, 0);
+        // 整体检测只能选出一种编码，必然有一部分被错误解码
+        assert_ne!(decode(&bytes).text, decoded.text);
+
+        // UTF-16 按整体解码
+        let mut wide = vec![0xFF, 0xFE];
+        wide.extend(utf16("a\nb\n", true));
+        let decoded = decode_per_line(&wide);
+        assert_eq!(decoded.text, "a\nb\n");
+        assert_eq!(decoded.line_encodings, [TextEncoding::Other(UTF_16LE); 2]);
+        assert_eq!(decode_per_line(b"").line_encodings, []);
+    }
+
+    #[test]
+    fn test_classify_truncated_surrogate_pair() {
+        // 代理对跨越 8KB 样本边界的 UTF-16LE 文本
+        let text = format!("{}😀{}", "a".repeat(CLASSIFY_SAMPLE_LEN / 2 - 1), "tail");
+        let bytes: Vec<u8> = text
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        assert_eq!(classify_bytes(&bytes), FileKind::Text);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wide.txt");
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(classify(&path).unwrap(), FileKind::Text);
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify_bytes(b""), FileKind::Empty);
+        assert_eq!(
+            classify_bytes("普通文本\r\n\tline".as_bytes()),
+            FileKind::Text
+        );
+        assert_eq!(
+            classify_bytes(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            FileKind::Binary
+        );
+        // UTF-16 文本中的空字节不应被当成二进制
+        let utf16: Vec<u8> = "subtitle text line"
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        assert_eq!(classify_bytes(&utf16), FileKind::Text);
+        assert_eq!(
+            classify_bytes(&[0x01, 0x02, 0x03, b'a', 0x04]),
+            FileKind::Binary
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "文本").unwrap();
+        fs::write(dir.path().join("b.bin"), [0x7F, b'E', b'L', b'F', 0, 0, 1]).unwrap();
+        fs::write(dir.path().join("c.txt"), "").unwrap();
+        assert_eq!(
+            classify(dir.path().join("b.bin")).unwrap(),
+            FileKind::Binary
+        );
+        assert_eq!(classify(dir.path().join("c.txt")).unwrap(), FileKind::Empty);
+
+        let results = decode_directory(dir.path(), DecodeMode::Lossy).unwrap();
+        assert_eq!(results[0].1.as_ref().unwrap().text, "文本");
+        assert!(matches!(
+            &results[1].1,
+            Err(DecodeError::BinaryContent { path }) if path.ends_with("b.bin")
+        ));
+        assert_eq!(results[2].1.as_ref().unwrap().text, "");
+    }
+
+    #[test]
+    fn test_normalize_newlines() {
+        let mut decoded = decode(b"a\r\nb\rc\nd");
+        assert!(decoded.normalize_newlines(NewlineStyle::Lf));
+        assert_eq!(decoded.text, "a\nb\nc\nd");
+        assert!(!decoded.normalize_newlines(NewlineStyle::Lf));
+
+        assert!(decoded.normalize_newlines(NewlineStyle::CrLf));
+        assert_eq!(decoded.text, "a\r\nb\r\nc\r\nd");
+        assert!(!decoded.normalize_newlines(NewlineStyle::CrLf));
+
+        let mut plain = decode(b"no newline");
+        assert!(!plain.normalize_newlines(NewlineStyle::CrLf));
+    }
+
+    #[test]
+    fn test_decode_file_with_priors() {
+        let dir = tempfile::tempdir().unwrap();
+        // 很短的 GBK 文本，单凭内容无法可靠判断
+        let bytes = GBK.encode("你好").0;
+        let path = dir.path().join("short.ass");
+        fs::write(&path, &bytes).unwrap();
+
+        let mut priors = ExtensionPriors::default();
+        priors.insert("ass", GBK);
+        let hinted = decode_file_with_priors(&path, &priors).unwrap();
+        assert_eq!(hinted.decoded.text, "你好");
+        assert_eq!(hinted.decoded.encoding, TextEncoding::Other(GBK));
+
+        // 扩展名没有先验时只按内容检测
+        let plain = dir.path().join("short.bin");
+        fs::write(&plain, &bytes).unwrap();
+        let hinted = decode_file_with_priors(&plain, &priors).unwrap();
+        assert!(!hinted.hint_applied);
+        assert_eq!(hinted.decoded.encoding, hinted.detection.encoding);
+    }
+}
@@ -0,0 +1,82 @@
+use encoding_rs::{Encoding, UTF_8};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 扩展名 -> 常见编码的先验表
+///
+/// 只作为弱提示：检测置信度足够时仍以内容为准，短文件检测不可靠时才采用先验
+/// （见 [`decode_with_hint`](super::decode_with_hint)）。默认表只收录几乎总是 UTF-8 的格式；
+/// `.ass`、`.txt` 等编码差异很大的格式不设先验。
+#[derive(Debug, Clone)]
+pub struct ExtensionPriors {
+    map: HashMap<String, &'static Encoding>,
+}
+
+impl Default for ExtensionPriors {
+    fn default() -> Self {
+        let mut priors = Self::new();
+        for ext in [
+            "po", "pot", "srt", "vtt", "json", "md", "toml", "yaml", "yml",
+        ] {
+            priors.insert(ext, UTF_8);
+        }
+        priors
+    }
+}
+
+impl ExtensionPriors {
+    /// 空表
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    /// 添加或替换先验（扩展名大小写不敏感，可带前导点），返回原有的编码
+    pub fn insert(
+        &mut self,
+        extension: &str,
+        encoding: &'static Encoding,
+    ) -> Option<&'static Encoding> {
+        self.map.insert(normalize_extension(extension), encoding)
+    }
+
+    /// 删除先验，返回原有的编码
+    pub fn remove(&mut self, extension: &str) -> Option<&'static Encoding> {
+        self.map.remove(&normalize_extension(extension))
+    }
+
+    /// 扩展名对应的先验编码
+    pub fn get(&self, extension: &str) -> Option<&'static Encoding> {
+        self.map.get(&normalize_extension(extension)).copied()
+    }
+
+    /// 按路径的扩展名查找先验编码
+    pub fn for_path(&self, path: &Path) -> Option<&'static Encoding> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.get(ext))
+    }
+}
+
+fn normalize_extension(ext: &str) -> String {
+    ext.trim_start_matches('.').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::GBK;
+
+    #[test]
+    fn test_priors_table() {
+        let mut priors = ExtensionPriors::default();
+        assert_eq!(priors.get(".SRT"), Some(UTF_8));
+        assert_eq!(priors.get("ass"), None);
+        assert_eq!(priors.insert("ASS", GBK), None);
+        assert_eq!(priors.for_path(Path::new("dir/sub.ass")), Some(GBK));
+        assert_eq!(priors.remove(".srt"), Some(UTF_8));
+        assert_eq!(priors.for_path(Path::new("a.srt")), None);
+        assert_eq!(priors.for_path(Path::new("noext")), None);
+    }
+}
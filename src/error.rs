@@ -0,0 +1,138 @@
+use std::{io, path::PathBuf};
+use thiserror::Error;
+use zip::result::ZipError;
+
+/// 被拒绝的不安全路径：归档试图写到哪里，以及被哪一部分拒绝
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("条目 {entry:?} 的路径不安全：{component:?} 会让它写到 {}", .resolved.display())]
+pub struct UnsafePathError {
+    /// 归档中的原始条目名（检查子目录或改名目标时为该名称）
+    pub entry: String,
+    /// 导致拒绝的路径部分，如 `..`、`/` 或 `C:`；名称为空时为空字符串
+    pub component: String,
+    /// 不做清理时实际会写到的路径（按字面解析 `..`，不访问文件系统）
+    pub resolved: PathBuf,
+}
+
+/// 文件模块（重命名并打开文件）中可能出现的错误
+#[derive(Debug, Error)]
+pub enum FileOpError {
+    /// 按扩展名规则处理时文件没有扩展名
+    #[error("文件 {} 没有扩展名", .0.display())]
+    NoExtension(PathBuf),
+    /// 重命名失败，包括目标目录不存在
+    #[error("无法将 {} 重命名为 {}: {source}", .from.display(), .to.display())]
+    RenameFailed {
+        from: PathBuf,
+        to: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// 打开（重命名后的）文件失败
+    #[error("无法打开文件 {}: {source}", .path.display())]
+    OpenFailed {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// 路径既不是文件也不是目录
+    #[error("路径 {} 不是文件也不是目录", .0.display())]
+    NotFileOrDir(PathBuf),
+    /// 读取元数据、目录或文件内容等其他操作失败；路径不存在时 `source` 的类型为 `NotFound`
+    #[error("访问 {} 失败: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// 解压与解码过程中可能出现的错误
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error(transparent)]
+    Zip(#[from] ZipError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// 两个条目仅大小写或 Unicode 规范化形式不同，解压后会互相覆盖
+    #[error("条目 {second:?} 与 {first:?} 仅大小写或 Unicode 规范化不同")]
+    CaseCollision { first: String, second: String },
+    /// 条目名试图跳出输出目录（严格路径检查下的 `..`、被拒绝的绝对路径等）
+    #[error(transparent)]
+    UnsafePath(UnsafePathError),
+    /// 覆盖策略为 [`OverwritePolicy::Error`](crate::files::extractor::OverwritePolicy::Error) 时目标文件已存在
+    #[error("目标文件 {} 已存在", .0.display())]
+    AlreadyExists(PathBuf),
+    /// 解压后数据的 CRC32 与中央目录记录的不一致
+    #[error("条目 {entry:?} CRC 校验失败: 期望 {expected:08x}, 实际 {actual:08x}")]
+    CrcMismatch {
+        entry: String,
+        expected: u32,
+        actual: u32,
+    },
+    /// 解压后数据的摘要与外部清单中的不一致
+    #[error("条目 {entry:?} {algorithm} 校验失败: 期望 {expected}, 实际 {actual}")]
+    DigestMismatch {
+        entry: String,
+        algorithm: &'static str,
+        expected: String,
+        actual: String,
+    },
+    /// 条目数据无法读取或解压（压缩流损坏等）
+    #[error("条目 {entry:?} 已损坏: {source}")]
+    CorruptEntry {
+        entry: String,
+        #[source]
+        source: io::Error,
+    },
+    /// 条目实际解压出的字节数与中央目录声明的大小不符
+    #[error("条目 {entry:?} 声明大小为 {declared} 字节，实际解压出 {actual} 字节")]
+    EntrySizeMismatch {
+        entry: String,
+        declared: u64,
+        actual: u64,
+    },
+    /// 实际解压出的总字节数超过上限
+    #[error("解压总大小超过上限 {limit} 字节")]
+    TotalSizeExceeded { limit: u64 },
+    /// 中央目录中的条目数超过上限
+    #[error("归档包含 {found} 个条目，超过上限 {limit}")]
+    TooManyEntries { limit: usize, found: usize },
+    /// 下载归档失败
+    #[cfg(feature = "http")]
+    #[error("下载失败: {0}")]
+    Http(#[source] Box<ureq::Error>),
+    /// 并行解压时工作线程在处理某个条目时 panic
+    #[error("解压条目 {entry:?} 时工作线程 panic: {message}")]
+    WorkerPanicked { entry: String, message: String },
+    /// 按索引解压时部分索引超出条目数
+    #[error("索引 {indices:?} 超出范围，归档只有 {len} 个条目")]
+    IndexOutOfRange { indices: Vec<usize>, len: usize },
+    /// 条目实际解压字节数与压缩大小之比超过上限
+    #[error("条目 {entry:?} 压缩比超过上限 {limit}")]
+    CompressionRatioExceeded { entry: String, limit: f64 },
+    /// 输出路径超过 Windows 的 MAX_PATH 且未开启长路径前缀
+    #[error("条目 {entry:?} 的输出路径 {} 过长，可开启 long_paths", .path.display())]
+    PathTooLong { entry: String, path: PathBuf },
+    /// 输入中找不到中央目录结束记录（例如并非 ZIP 格式的安装程序）
+    #[error("找不到 ZIP 中央目录结束记录，输入不是 ZIP 或自解压 ZIP 文件")]
+    NotAnArchive,
+    /// 归档在末尾被截断（如下载中断）：以 ZIP 本地文件头开头但缺少中央目录
+    #[error("归档不完整（仅 {size} 字节），可能下载或复制时被中断")]
+    TruncatedArchive { size: u64 },
+    /// 指定的偏移处不是 ZIP 签名（`PK\x03\x04` 或空归档的 `PK\x05\x06`）
+    #[error("偏移 {offset} 处没有 ZIP 文件头")]
+    NoArchiveAtOffset { offset: u64 },
+    /// 分卷归档的分卷缺失、顺序错误或大小不符
+    #[error("分卷无效: {reason}")]
+    InvalidSegments { reason: String },
+    /// 文件内容是二进制，没有做编码检测
+    #[error("文件 {path:?} 是二进制文件，不做编码检测")]
+    BinaryContent { path: PathBuf },
+    /// 严格解码模式下遇到非法字节序列
+    #[error("按 {encoding} 解码失败: 偏移 {offset} 处存在非法字节序列")]
+    InvalidSequence {
+        encoding: &'static str,
+        offset: usize,
+    },
+}
@@ -0,0 +1,116 @@
+use crate::DecodeError;
+use crate::decode::{NewlineStyle, TextEncoding, decode};
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// 文件模块返回的已打开文件：路径、句柄以及（读取后）检测出的编码
+#[derive(Debug)]
+pub struct DecodedFile {
+    path: PathBuf,
+    file: File,
+    encoding: Option<TextEncoding>,
+    newlines: Option<NewlineStyle>,
+    newlines_converted: bool,
+}
+
+impl DecodedFile {
+    /// 打开文件，此时尚未检测编码
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+        Ok(Self {
+            path,
+            file,
+            encoding: None,
+            newlines: None,
+            newlines_converted: false,
+        })
+    }
+
+    /// 解码时把换行符统一为 `style`，省去之后再改写一遍
+    pub fn normalize_newlines(mut self, style: NewlineStyle) -> Self {
+        self.newlines = Some(style);
+        self
+    }
+
+    /// 文件路径（重命名后为新路径）
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 底层文件句柄
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    /// 最近一次 [`read_to_string_decoded`](Self::read_to_string_decoded) 检测出的编码
+    pub fn encoding(&self) -> Option<TextEncoding> {
+        self.encoding
+    }
+
+    /// 最近一次读取时是否转换过换行符
+    ///
+    /// 未设置 [`normalize_newlines`](Self::normalize_newlines) 时恒为假。
+    pub fn newlines_converted(&self) -> bool {
+        self.newlines_converted
+    }
+
+    /// 从当前位置读到末尾，自动检测编码并转换为 UTF-8（非法序列替换为 U+FFFD）
+    pub fn read_to_string_decoded(&mut self) -> Result<String, DecodeError> {
+        let mut bytes = Vec::new();
+        self.file.read_to_end(&mut bytes)?;
+        let mut decoded = decode(&bytes);
+        self.encoding = Some(decoded.encoding);
+        self.newlines_converted = self
+            .newlines
+            .is_some_and(|style| decoded.normalize_newlines(style));
+        Ok(decoded.text)
+    }
+
+    /// 取出底层文件句柄
+    pub fn into_file(self) -> File {
+        self.file
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::GBK;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_to_string_decoded() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("gbk.txt");
+        let text = "这是一段用国标编码保存的中文字幕文件内容，用来测试编码检测。";
+        fs::write(&path, GBK.encode(text).0).unwrap();
+
+        let mut file = DecodedFile::open(&path).unwrap();
+        assert_eq!(file.path(), path);
+        assert_eq!(file.encoding(), None);
+        assert_eq!(file.read_to_string_decoded().unwrap(), text);
+        assert_eq!(file.encoding(), Some(TextEncoding::Other(GBK)));
+        assert!(file.into_file().metadata().unwrap().len() > 0);
+    }
+
+    #[test]
+    fn test_normalize_newlines_on_read() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("crlf.txt");
+        fs::write(&path, b"line one\r\nline two\r\n").unwrap();
+
+        let mut file = DecodedFile::open(&path)
+            .unwrap()
+            .normalize_newlines(NewlineStyle::Lf);
+        assert_eq!(
+            file.read_to_string_decoded().unwrap(),
+            "line one\nline two\n"
+        );
+        assert!(file.newlines_converted());
+    }
+}
@@ -0,0 +1,86 @@
+use sha2::{Digest, Sha256};
+
+/// 按外部清单校验解压结果时使用的摘要算法
+///
+/// zip 只记录 CRC32，清单通常来自可信的发布方，可以使用更强的算法。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Crc32,
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// 算法名称，用于错误信息
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Crc32 => "CRC32",
+            HashAlgorithm::Sha256 => "SHA-256",
+            HashAlgorithm::Blake3 => "BLAKE3",
+        }
+    }
+
+    pub(crate) fn hasher(&self) -> RunningDigest {
+        match self {
+            HashAlgorithm::Crc32 => RunningDigest::Crc32(crc32fast::Hasher::new()),
+            HashAlgorithm::Sha256 => RunningDigest::Sha256(Sha256::new()),
+            HashAlgorithm::Blake3 => RunningDigest::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+}
+
+/// 在拷贝过程中增量计算的摘要
+pub(crate) enum RunningDigest {
+    Crc32(crc32fast::Hasher),
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl RunningDigest {
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            RunningDigest::Crc32(hasher) => hasher.update(data),
+            RunningDigest::Sha256(hasher) => hasher.update(data),
+            RunningDigest::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    /// 小写十六进制表示（CRC32 为 8 位）
+    pub(crate) fn finalize_hex(self) -> String {
+        match self {
+            RunningDigest::Crc32(hasher) => format!("{:08x}", hasher.finalize()),
+            RunningDigest::Sha256(hasher) => hasher
+                .finalize()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+            RunningDigest::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_digests() {
+        let digest = |algorithm: HashAlgorithm| {
+            let mut hasher = algorithm.hasher();
+            hasher.update(b"ab");
+            hasher.update(b"c");
+            hasher.finalize_hex()
+        };
+        assert_eq!(digest(HashAlgorithm::Crc32), "352441c2");
+        assert_eq!(
+            digest(HashAlgorithm::Sha256),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            digest(HashAlgorithm::Blake3),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use zip::read::ZipFile;
+
+/// 归档条目的元数据（来自中央目录）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryInfo {
+    /// 在中央目录中的索引
+    pub index: usize,
+    /// 归档内的原始条目名
+    pub name: String,
+    /// 解压后大小 (字节)
+    pub size: u64,
+    /// 压缩后大小 (字节)
+    pub compressed_size: u64,
+    /// 记录的 CRC32
+    pub crc32: u32,
+    /// 是否为目录条目
+    pub is_dir: bool,
+}
+
+impl EntryInfo {
+    pub(crate) fn from_zip(index: usize, file: &ZipFile<'_>) -> Self {
+        Self {
+            index,
+            name: file.name().to_string(),
+            size: file.size(),
+            compressed_size: file.compressed_size(),
+            crc32: file.crc32(),
+            is_dir: file.is_dir(),
+        }
+    }
+}
+
+/// 归档整体的格式信息（只读取中央目录）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveInfo {
+    /// 条目总数
+    pub entries: usize,
+    /// 所有条目中解压所需的最高版本（如 20 表示 2.0，45 表示 ZIP64），空归档为 0
+    pub version_needed: u16,
+    /// 是否使用 ZIP64（ZIP64 中央目录结束记录或条目的 ZIP64 扩展字段）
+    pub zip64: bool,
+    /// 是否有任何条目被加密
+    pub encrypted: bool,
+}
+
+/// [`ZipExtractor::diff`](super::extractor::ZipExtractor::diff) 的结果，各列表按条目名排序
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveDiff {
+    /// 只在新归档中出现的条目
+    pub added: Vec<EntryInfo>,
+    /// 只在旧归档中出现的条目
+    pub removed: Vec<EntryInfo>,
+    /// 同名但 CRC32 或大小不同的条目：`(旧, 新)`
+    pub changed: Vec<(EntryInfo, EntryInfo)>,
+}
+
+impl ArchiveDiff {
+    /// 两个归档的条目是否完全相同
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// [`ZipExtractor::tree`](super::extractor::ZipExtractor::tree) 返回的目录节点
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirNode {
+    /// 目录名（根节点为空字符串）
+    pub name: String,
+    /// 目录下所有文件的解压后总大小 (字节)
+    pub size: u64,
+    /// 子目录，按名称排序
+    pub dirs: Vec<DirNode>,
+    /// 直接位于该目录下的文件，按名称排序
+    pub files: Vec<FileNode>,
+}
+
+/// 目录树中的文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileNode {
+    /// 文件名（不含目录部分）
+    pub name: String,
+    /// 在中央目录中的索引
+    pub index: usize,
+    /// 解压后大小 (字节)
+    pub size: u64,
+    /// 压缩后大小 (字节)
+    pub compressed_size: u64,
+}
+
+impl DirNode {
+    /// 由平铺的条目列表构建目录树
+    ///
+    /// 只有隐含在文件路径中的目录也会出现；空的路径组件与 `.` 会被忽略。
+    pub fn from_entries(entries: &[EntryInfo]) -> Self {
+        let mut root = DirNode::default();
+        for entry in entries {
+            let mut parts: Vec<&str> = entry
+                .name
+                .split(['/', '\\'])
+                .filter(|part| !part.is_empty() && *part != ".")
+                .collect();
+            let file_name = if entry.is_dir { None } else { parts.pop() };
+            let size = file_name.map_or(0, |_| entry.size);
+            let mut dir = &mut root;
+            dir.size += size;
+            for part in parts {
+                dir = dir.child(part);
+                dir.size += size;
+            }
+            if let Some(name) = file_name {
+                dir.files.push(FileNode {
+                    name: name.to_string(),
+                    index: entry.index,
+                    size: entry.size,
+                    compressed_size: entry.compressed_size,
+                });
+            }
+        }
+        root.sort();
+        root
+    }
+
+    /// 按相对路径（以 `/` 分隔）查找子目录
+    pub fn find(&self, path: &str) -> Option<&DirNode> {
+        path.split('/')
+            .filter(|part| !part.is_empty())
+            .try_fold(self, |dir, part| dir.dirs.iter().find(|d| d.name == part))
+    }
+
+    fn child(&mut self, name: &str) -> &mut DirNode {
+        let at = match self.dirs.iter().position(|d| d.name == name) {
+            Some(at) => at,
+            None => {
+                self.dirs.push(DirNode {
+                    name: name.to_string(),
+                    ..Default::default()
+                });
+                self.dirs.len() - 1
+            }
+        };
+        &mut self.dirs[at]
+    }
+
+    fn sort(&mut self) {
+        self.dirs.sort_by(|a, b| a.name.cmp(&b.name));
+        self.files.sort_by(|a, b| a.name.cmp(&b.name));
+        for dir in &mut self.dirs {
+            dir.sort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(index: usize, name: &str, size: u64) -> EntryInfo {
+        EntryInfo {
+            index,
+            name: name.to_string(),
+            size,
+            compressed_size: size / 2,
+            crc32: 0,
+            is_dir: name.ends_with('/'),
+        }
+    }
+
+    #[test]
+    fn test_dir_tree() {
+        let tree = DirNode::from_entries(&[
+            entry(0, "readme.md", 10),
+            entry(1, "src/", 0),
+            entry(2, "src/main.rs", 100),
+            entry(3, "src/util/mod.rs", 50),
+            entry(4, "assets/img/logo.png", 1000),
+            entry(5, "empty/", 0),
+        ]);
+
+        assert_eq!(tree.name, "");
+        assert_eq!(tree.size, 1160);
+        let names: Vec<_> = tree.dirs.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["assets", "empty", "src"]);
+        assert_eq!(tree.files.len(), 1);
+        assert_eq!(tree.files[0].name, "readme.md");
+
+        let src = tree.find("src").unwrap();
+        assert_eq!(src.size, 150);
+        assert_eq!(src.files[0].index, 2);
+        assert_eq!(tree.find("src/util").unwrap().files[0].name, "mod.rs");
+        // 只隐含在文件路径中的目录
+        assert_eq!(tree.find("assets/img").unwrap().size, 1000);
+        assert!(tree.find("empty").unwrap().files.is_empty());
+        assert!(tree.find("missing").is_none());
+    }
+}
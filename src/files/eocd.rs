@@ -0,0 +1,205 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// 中央目录结束记录的签名 `PK\x05\x06`
+pub(crate) const EOCD_SIGNATURE: u32 = 0x06054b50;
+/// 不含注释的记录长度
+const EOCD_LEN: usize = 22;
+/// ZIP64 中央目录结束定位符的签名 `PK\x06\x07`
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+/// ZIP64 定位符长度，紧挨在传统记录之前
+const ZIP64_LOCATOR_LEN: u64 = 20;
+/// 本地文件头的签名 `PK\x03\x04`
+pub(crate) const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+/// 中央目录文件头的签名 `PK\x01\x02`
+pub(crate) const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+/// 中央目录文件头中不含变长字段的部分
+pub(crate) const CENTRAL_HEADER_LEN: usize = 46;
+
+/// ZIP 中央目录结束记录（EOCD）
+///
+/// 只解析传统记录；ZIP64 归档中这里的计数和偏移可能是 `0xFFFF`/`0xFFFFFFFF` 占位值。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndOfCentralDirectory {
+    /// 记录在输入中的起始偏移
+    pub position: u64,
+    /// 本分卷的编号
+    pub disk_number: u16,
+    /// 中央目录起始所在的分卷编号
+    pub central_directory_disk: u16,
+    /// 本分卷上的条目数
+    pub entries_on_disk: u16,
+    /// 条目总数
+    pub entries: u16,
+    /// 中央目录的字节数
+    pub central_directory_size: u32,
+    /// 中央目录相对于归档起点的偏移
+    pub central_directory_offset: u32,
+    /// 归档注释
+    pub comment: Vec<u8>,
+}
+
+impl EndOfCentralDirectory {
+    /// 从输入末尾向前扫描中央目录结束记录，找不到时返回 `None`
+    ///
+    /// 记录之后最多有 65535 字节的注释，因此只需检查末尾的这段范围。优先选择注释长度
+    /// 恰好延伸到输入末尾的候选，以免把注释里的 `PK\x05\x06` 误认为记录。
+    pub fn find<R: Read + Seek>(reader: &mut R) -> io::Result<Option<Self>> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        let tail_len = len.min((EOCD_LEN + u16::MAX as usize) as u64);
+        let tail_start = len - tail_len;
+        reader.seek(SeekFrom::Start(tail_start))?;
+        let mut tail = vec![0u8; tail_len as usize];
+        reader.read_exact(&mut tail)?;
+
+        let mut fallback = None;
+        for i in (0..tail.len().saturating_sub(EOCD_LEN - 1)).rev() {
+            if read_u32(&tail, i) != EOCD_SIGNATURE {
+                continue;
+            }
+            let comment_end = i + EOCD_LEN + read_u16(&tail, i + 20) as usize;
+            if comment_end == tail.len() {
+                return Ok(Some(Self::parse(&tail, i, tail_start)));
+            }
+            if comment_end < tail.len() && fallback.is_none() {
+                fallback = Some(i);
+            }
+        }
+        Ok(fallback.map(|i| Self::parse(&tail, i, tail_start)))
+    }
+
+    /// 归档数据之前的前导字节数（如自解压程序的可执行存根），普通 ZIP 为 0
+    pub fn prefix_len(&self) -> u64 {
+        self.position.saturating_sub(
+            self.central_directory_size as u64 + self.central_directory_offset as u64,
+        )
+    }
+
+    /// 声明的中央目录是否完整位于记录之前（ZIP64 占位值无法在此判断，视为完整）
+    pub fn central_directory_fits(&self) -> bool {
+        if self.central_directory_offset == u32::MAX || self.central_directory_size == u32::MAX {
+            return true;
+        }
+        self.central_directory_offset as u64 + self.central_directory_size as u64 <= self.position
+    }
+
+    /// 记录之前是否紧跟 ZIP64 定位符（即归档使用 ZIP64 中央目录结束记录）
+    pub fn has_zip64_locator<R: Read + Seek>(&self, reader: &mut R) -> io::Result<bool> {
+        let Some(at) = self.position.checked_sub(ZIP64_LOCATOR_LEN) else {
+            return Ok(false);
+        };
+        reader.seek(SeekFrom::Start(at))?;
+        let mut signature = [0u8; 4];
+        reader.read_exact(&mut signature)?;
+        Ok(u32::from_le_bytes(signature) == ZIP64_LOCATOR_SIGNATURE)
+    }
+
+    fn parse(buf: &[u8], at: usize, base: u64) -> Self {
+        let comment_len = read_u16(buf, at + 20) as usize;
+        Self {
+            position: base + at as u64,
+            disk_number: read_u16(buf, at + 4),
+            central_directory_disk: read_u16(buf, at + 6),
+            entries_on_disk: read_u16(buf, at + 8),
+            entries: read_u16(buf, at + 10),
+            central_directory_size: read_u32(buf, at + 12),
+            central_directory_offset: read_u32(buf, at + 16),
+            comment: buf[at + EOCD_LEN..at + EOCD_LEN + comment_len].to_vec(),
+        }
+    }
+}
+
+/// 中央目录文件头中 zip 库未公开的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CentralHeaderFields {
+    /// 解压所需的最低版本（如 20 表示 2.0，45 表示 ZIP64）
+    pub version_needed: u16,
+    /// 通用标志位
+    pub flags: u16,
+}
+
+impl CentralHeaderFields {
+    /// 读取位于 `position` 的中央目录文件头
+    pub fn read_at<R: Read + Seek>(reader: &mut R, position: u64) -> io::Result<Self> {
+        reader.seek(SeekFrom::Start(position))?;
+        let mut header = [0u8; CENTRAL_HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        if read_u32(&header, 0) != CENTRAL_HEADER_SIGNATURE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("偏移 {} 处不是中央目录文件头", position),
+            ));
+        }
+        Ok(Self {
+            version_needed: read_u16(&header, 6),
+            flags: read_u16(&header, 8),
+        })
+    }
+
+    /// 标志位 0 表示条目已加密
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & 0x0001 != 0
+    }
+}
+
+/// 扩展字段中是否包含 ZIP64 扩展信息（header id 0x0001）
+pub fn has_zip64_extra(extra: &[u8]) -> bool {
+    let mut at = 0;
+    while at + 4 <= extra.len() {
+        if read_u16(extra, at) == 0x0001 {
+            return true;
+        }
+        at += 4 + read_u16(extra, at + 2) as usize;
+    }
+    false
+}
+
+pub(crate) fn read_u16(buf: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([buf[at], buf[at + 1]])
+}
+
+pub(crate) fn read_u32(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::{FileOptions, ZipWriter};
+
+    fn zip_bytes(comment: &str) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.set_comment(comment);
+        writer.start_file("a.txt", FileOptions::default()).unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_find_eocd() {
+        // 注释中伪造的签名不应被选中
+        let bytes = zip_bytes("fake PK\x05\x06 inside comment");
+        let eocd = EndOfCentralDirectory::find(&mut Cursor::new(&bytes))
+            .unwrap()
+            .unwrap();
+        assert_eq!(eocd.entries, 1);
+        assert_eq!(eocd.comment, b"fake PK\x05\x06 inside comment");
+        assert_eq!(
+            eocd.position as usize + EOCD_LEN + eocd.comment.len(),
+            bytes.len()
+        );
+        assert_eq!(eocd.prefix_len(), 0);
+
+        let mut sfx = vec![0x90u8; 1000];
+        sfx.extend_from_slice(&bytes);
+        let eocd = EndOfCentralDirectory::find(&mut Cursor::new(&sfx))
+            .unwrap()
+            .unwrap();
+        assert_eq!(eocd.prefix_len(), 1000);
+
+        assert_eq!(
+            EndOfCentralDirectory::find(&mut Cursor::new(b"MZ not a zip")).unwrap(),
+            None
+        );
+    }
+}
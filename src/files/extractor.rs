@@ -1,36 +1,405 @@
+use super::digest::HashAlgorithm;
+use super::entry::{ArchiveDiff, ArchiveInfo, DirNode, EntryInfo};
+use super::eocd::{self, CentralHeaderFields, EndOfCentralDirectory};
+use super::report::{ExtractionReport, Mismatch, MismatchKind, PartialExtraction, SkipReason};
+use super::segments::SegmentedSource;
+use super::source::{ArchiveSource, FileSource, MmapSource, ReadSeek, ReaderSource};
+use crate::decode::{self, Decoded, ExtensionPriors};
+use crate::{DecodeError, UnsafePathError};
 use std::{
+    any::Any,
+    cell::Cell,
+    collections::{BTreeMap, HashMap, HashSet},
     fs::{File, OpenOptions},
-    io::{self, BufReader, BufWriter, Read, Write},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    panic::{self, AssertUnwindSafe},
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
-    time::Instant,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, SyncSender},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use zip::{read::ZipArchive, result::ZipError};
+use unicode_normalization::UnicodeNormalization;
+use zip::{
+    CompressionMethod,
+    read::{ZipArchive, ZipFile},
+    result::ZipError,
+};
+
+/// 条目名仅大小写或 Unicode 规范化不同时的处理方式
+///
+/// 在大小写不敏感的文件系统上，这类条目解压后会互相覆盖。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CollisionPolicy {
+    /// 不检测，后出现的条目覆盖先出现的
+    #[default]
+    Allow,
+    /// 输出警告并跳过后出现的条目，记为 [`SkipReason::CaseCollision`]
+    Skip,
+    /// 返回 [`DecodeError::CaseCollision`]，不写出任何文件
+    Error,
+}
+
+/// 绝对路径条目（以 `/`、`\` 或 Windows 盘符开头，如 `/etc/passwd`）的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AbsolutePathPolicy {
+    /// 去掉开头的分隔符与盘符后解压到输出目录内，并记入报告的 `relativized`
+    #[default]
+    Relativize,
+    /// 跳过该条目，记为 [`SkipReason::AbsolutePath`]
+    Skip,
+    /// 返回 [`DecodeError::UnsafePath`]，不写出任何文件
+    Reject,
+}
+
+/// 目标文件已存在时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    /// 直接覆盖
+    #[default]
+    Overwrite,
+    /// 保留已有文件，条目记为 [`SkipReason::AlreadyExists`]
+    Skip,
+    /// 返回 [`DecodeError::AlreadyExists`]
+    Error,
+    /// 条目的修改时间晚于已有文件时才覆盖，否则记为 [`SkipReason::NotNewer`]
+    ///
+    /// 条目时间按 UTC 解释（zip 记录的是不带时区的本地时间，精度 2 秒）；
+    /// 没有记录时间的条目总是覆盖。
+    IfNewer,
+    /// 已有文件的内容与条目不同时才覆盖，相同则记为 [`SkipReason::Unchanged`]
+    ///
+    /// 先比较大小，大小相同时计算已有文件的 CRC32 与中央目录比较，与修改时间无关。
+    /// 适合增量解压，内容未变的大文件不会被重写。
+    IfDifferent,
+}
+
+impl OverwritePolicy {
+    /// 写出时是否替换已存在的目标文件（`IfNewer`、`IfDifferent` 已在写出前排除了不需要覆盖的条目）
+    fn replaces_existing(self) -> bool {
+        matches!(
+            self,
+            OverwritePolicy::Overwrite | OverwritePolicy::IfNewer | OverwritePolicy::IfDifferent
+        )
+    }
+}
+
+/// [`ZipExtractor::on_conflict`] 回调对单个已存在目标文件的处理决定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// 覆盖已有文件（按 [`OutputMode`] 打开）
+    Overwrite,
+    /// 保留已有文件，条目记为 [`SkipReason::AlreadyExists`]
+    Skip,
+    /// 改为写出到另一个路径；相对路径相对于冲突文件所在的目录，且不能含 `..`
+    ///
+    /// 新路径同样已存在时返回 [`DecodeError::AlreadyExists`]，不会再次调用回调。
+    Rename(PathBuf),
+    /// 返回 [`DecodeError::AlreadyExists`] 中止解压
+    Abort,
+}
+
+/// 覆盖已有文件时打开输出文件的方式
+///
+/// 仅在会替换已有文件的覆盖策略（`Overwrite`、`IfNewer`、`IfDifferent`）下生效。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// 截断后从头写入
+    #[default]
+    Truncate,
+    /// 追加到已有内容之后
+    Append,
+    /// 不截断，从指定偏移开始覆写（超出原长度的部分会扩展文件）
+    At(u64),
+}
+
+/// 按字节统计的解压进度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// 已写出的解压后字节数
+    pub bytes_done: u64,
+    /// 归档中全部条目声明的解压后总字节数
+    pub bytes_total: u64,
+}
+
+type ProgressFn = dyn Fn(Progress) + Send + Sync;
+type NameTransformFn = dyn Fn(&str) -> Option<String> + Send + Sync;
+type EntryStartFn = dyn Fn(&EntryInfo) + Send + Sync;
+type EntryDoneFn = dyn Fn(&EntryInfo, &Path) + Send + Sync;
+type BufferSizerFn = dyn Fn(&EntryInfo) -> (usize, usize) + Send + Sync;
+type ConflictFn = dyn Fn(&Path, &EntryInfo) -> ConflictResolution + Send + Sync;
+
+/// [`ZipExtractor::into_events`] 产生的解压事件
+#[derive(Debug)]
+pub enum ExtractEvent {
+    /// 解压开始
+    Started,
+    /// 一个条目（文件或目录）已写出
+    EntryExtracted(EntryInfo),
+    /// 一个条目被跳过
+    EntrySkipped(String, SkipReason),
+    /// 解压完成，总是最后一个事件
+    Finished(ExtractionReport),
+    /// 解压失败，代替 `Finished` 作为最后一个事件
+    Failed(DecodeError),
+}
+
+/// [`ZipExtractor::extract_to_memory`] 返回的单个条目数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryData {
+    /// 不超过阈值的条目直接保存在内存中
+    InMemory(Vec<u8>),
+    /// 超过阈值的条目写入的临时文件，由调用方负责删除
+    OnDisk(PathBuf),
+}
+
+/// [`ZipExtractor::extract_decoded`] 返回的单个条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodedEntry {
+    /// 扩展名属于文本类型的条目，已解码为 UTF-8
+    Text(Decoded),
+    /// 其余条目，保持原始字节（超过阈值时同样转存到临时文件）
+    Binary(EntryData),
+}
 
 /// 高性能 ZIP 解压器（专为大文件优化）
 pub struct ZipExtractor {
     /// 输入 ZIP 文件路径
     zip_path: PathBuf,
+    /// 自定义数据源（未设置时按 `zip_path` 打开文件）
+    source: Option<Arc<dyn ArchiveSource>>,
     /// 输出目录
     output_dir: PathBuf,
     /// 读缓冲区大小 (字节)
     read_buffer_size: usize,
     /// 写缓冲区大小 (字节)
     write_buffer_size: usize,
+    /// 写出每个文件后是否丢弃其页缓存
+    drop_page_cache: bool,
+    /// 是否按实测吞吐量自动选择缓冲区大小
+    adaptive_buffers: bool,
+    /// 是否把文件重新压缩为 gzip 写出到 `<路径>.gz`
+    gzip_output: bool,
     /// 并行解压线程数 (0=自动选择)
     worker_threads: usize,
+    /// 是否以内存映射方式读取输入文件
+    mmap: bool,
+    /// 是否强制按归档顺序单线程解压
+    preserve_order: bool,
+    /// 大小写/规范化冲突的处理方式
+    collisions: CollisionPolicy,
+    /// 是否拒绝含 `..` 的条目（关闭时静默剔除）
+    strict_paths: bool,
+    /// 绝对路径条目的处理方式
+    absolute_paths: AbsolutePathPolicy,
+    /// 目标文件已存在时的处理方式
+    overwrite: OverwritePolicy,
+    /// 目标文件已存在时逐个决定处理方式的回调（设置后代替 `overwrite`）
+    on_conflict: Option<Arc<ConflictFn>>,
+    /// 覆盖已有文件时的写入方式
+    output_mode: OutputMode,
+    /// 是否先写入临时文件再重命名到目标位置
+    atomic_writes: bool,
+    /// 临时文件所在目录（未设置时使用 `output_dir`）
+    temp_dir: Option<PathBuf>,
+    /// 是否校验解压数据的 CRC32
+    verify_crc: bool,
+    /// CRC 不匹配时是否记入报告并继续（否则立即中止）
+    collect_crc_errors: bool,
+    /// 按外部清单校验的算法与 条目名 -> 期望摘要
+    manifest: Option<(HashAlgorithm, Arc<HashMap<String, String>>)>,
+    /// 实际解压总字节数上限
+    max_total_size: Option<u64>,
+    /// 单个条目实际解压字节数与压缩大小之比的上限
+    max_compression_ratio: Option<f64>,
+    /// 压缩比超过该值（但未超过上限）的条目记为可疑
+    warn_compression_ratio: Option<f64>,
+    /// 条目数上限
+    max_entries: Option<usize>,
+    /// Windows 下是否为超长输出路径自动加 `\\?\` 前缀
+    long_paths: bool,
+    /// 报告中保留的最慢条目数（0 表示不记录）
+    slowest_entries: usize,
+    /// 输出目录下再套一层的子目录（未路由的条目都放在其中）
+    subdir: Option<String>,
+    /// 顶层目录名 -> 输出目录
+    routes: HashMap<String, PathBuf>,
+    /// 不匹配任何路由的条目是否跳过（否则解压到 `output_dir`）
+    skip_unrouted: bool,
+    /// 记录已完成条目的检查点文件
+    checkpoint: Option<PathBuf>,
+    /// 在内置路径清理之前对条目名做的转换
+    name_transform: Option<Arc<NameTransformFn>>,
+    /// 是否跳过 macOS 元数据条目
+    skip_mac_metadata: bool,
+    /// 是否把符号链接条目写成内容为链接目标的普通文件（否则跳过）
+    symlinks_as_files: bool,
+    /// 是否只创建目录结构而不写出文件
+    dirs_only: bool,
+    /// 是否在每个文件旁写出 `<文件名>.meta.json`
+    metadata_sidecars: bool,
+    /// 是否把输出路径转为小写
+    lowercase_names: bool,
+    /// `extract_to_memory` 中条目超过该字节数后转存到临时文件
+    spill_threshold: u64,
+    /// 允许写出的扩展名（小写、不含点），`None` 表示不限制
+    allowed_extensions: Option<HashSet<String>>,
+    /// 写出的文件条目的最小、最大解压后大小（含边界）
+    min_entry_size: Option<u64>,
+    max_entry_size: Option<u64>,
+    /// 允许写出的 MIME 类型（小写；以 `/` 结尾的表示整个大类），`None` 表示不限制
+    content_types: Option<Vec<String>>,
+    /// 按条目决定 (拷贝读块, 写缓冲) 大小，未设置时使用默认值
+    buffer_sizer: Option<Arc<BufferSizerFn>>,
+    /// 进度回调
+    progress: Option<Arc<ProgressFn>>,
+    /// 开始处理每个条目时的回调
+    entry_start: Option<Arc<EntryStartFn>>,
+    /// 每个条目写出后的回调
+    entry_done: Option<Arc<EntryDoneFn>>,
+    /// 两次进度回调之间至少间隔的字节数
+    progress_interval: u64,
+    /// 从 URL 下载的临时归档及其字节数，随解压器一起释放
+    download: Option<(Arc<TempFile>, u64)>,
+    /// 事件发送端（仅由 `into_events` 设置）
+    events: Option<SyncSender<ExtractEvent>>,
 }
 
 impl ZipExtractor {
     /// 创建新的解压器实例
-    pub fn new<P: AsRef<Path>>(zip_path: P, output_dir: P) -> Self {
+    pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(zip_path: P, output_dir: Q) -> Self {
         Self {
             zip_path: zip_path.as_ref().to_path_buf(),
+            source: None,
             output_dir: output_dir.as_ref().to_path_buf(),
             read_buffer_size: 2 * 1024 * 1024,  // 默认 2MB 读缓冲
             write_buffer_size: 4 * 1024 * 1024, // 默认 4MB 写缓冲
-            worker_threads: 0,                  // 自动选择线程数
+            drop_page_cache: false,
+            adaptive_buffers: false,
+            gzip_output: false,
+            worker_threads: 0, // 自动选择线程数
+            mmap: false,
+            preserve_order: false,
+            collisions: CollisionPolicy::Allow,
+            strict_paths: false,
+            absolute_paths: AbsolutePathPolicy::Relativize,
+            overwrite: OverwritePolicy::Overwrite,
+            on_conflict: None,
+            output_mode: OutputMode::Truncate,
+            atomic_writes: false,
+            temp_dir: None,
+            verify_crc: true,
+            collect_crc_errors: false,
+            manifest: None,
+            max_total_size: None,
+            max_compression_ratio: None,
+            warn_compression_ratio: None,
+            max_entries: None,
+            long_paths: false,
+            slowest_entries: 0,
+            subdir: None,
+            routes: HashMap::new(),
+            skip_unrouted: false,
+            checkpoint: None,
+            name_transform: None,
+            skip_mac_metadata: false,
+            symlinks_as_files: false,
+            dirs_only: false,
+            metadata_sidecars: false,
+            spill_threshold: 16 * 1024 * 1024, // 默认 16MB
+            allowed_extensions: None,
+            min_entry_size: None,
+            max_entry_size: None,
+            content_types: None,
+            lowercase_names: false,
+            buffer_sizer: None,
+            progress: None,
+            progress_interval: 1024 * 1024, // 默认每 1MB 回调一次
+            entry_start: None,
+            entry_done: None,
+            download: None,
+            events: None,
+        }
+    }
+
+    /// 解压到 ZIP 文件所在的目录（路径不含父目录时为当前目录）
+    ///
+    /// 目标目录通常已有其他文件，因此默认开启严格路径检查并使用 [`OverwritePolicy::Skip`]，
+    /// 可以再通过 builder 覆盖。
+    pub fn extract_here<P: AsRef<Path>>(zip_path: P) -> Self {
+        let zip_path = zip_path.as_ref();
+        let output_dir = match zip_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        Self::new(zip_path, output_dir)
+            .strict_paths(true)
+            .overwrite(OverwritePolicy::Skip)
+    }
+
+    /// 使用自定义数据源创建解压器（如内存映射、解密流等）
+    ///
+    /// 并行解压时每个工作线程都会调用一次 `source.open()` 获取独立句柄。
+    pub fn with_source<S, P>(source: S, output_dir: P) -> Self
+    where
+        S: ArchiveSource + 'static,
+        P: AsRef<Path>,
+    {
+        let mut extractor = Self::new(PathBuf::new(), output_dir);
+        extractor.source = Some(Arc::new(source));
+        extractor
+    }
+
+    /// 从 `reader` 中 `offset` 处开始的内嵌归档创建解压器
+    ///
+    /// 适用于 ZIP 数据位于更大文件中已知位置的情况（附加在存根后的 APK、游戏资源包等），
+    /// 比依赖自解压检测更精确。归档内记录的偏移按相对于 `offset` 解释；
+    /// `offset` 处不是 ZIP 签名时返回 [`DecodeError::NoArchiveAtOffset`]。
+    pub fn from_reader_at<R, P>(reader: R, offset: u64, output_dir: P) -> Result<Self, DecodeError>
+    where
+        R: Read + Seek + Send + 'static,
+        P: AsRef<Path>,
+    {
+        let source = ReaderSource::new(reader, offset)?;
+        let signature = u32::from_le_bytes(source.peek()?);
+        if signature != eocd::LOCAL_HEADER_SIGNATURE && signature != eocd::EOCD_SIGNATURE {
+            return Err(DecodeError::NoArchiveAtOffset { offset });
         }
+        Ok(Self::with_source(source, output_dir))
+    }
+
+    /// 从按顺序给出的分卷（`.z01`..`.zNN`，最后是 `.zip`）创建解压器
+    ///
+    /// 分卷在逻辑上拼接为一个归档，不需要事先合并；校验规则见 [`SegmentedSource::open`]。
+    pub fn from_segments<P: AsRef<Path>>(
+        paths: &[PathBuf],
+        output_dir: P,
+    ) -> Result<Self, DecodeError> {
+        Ok(Self::with_source(SegmentedSource::open(paths)?, output_dir))
+    }
+
+    /// 把 HTTP(S) URL 指向的归档下载到系统临时目录后创建解压器（需要 `http` feature）
+    ///
+    /// 下载在此函数中完成，之后的解压流程与本地文件完全相同；临时文件在解压器释放时删除。
+    /// 下载的字节数记入报告的 `downloaded_bytes`。
+    /// 服务器返回错误状态码时返回 [`DecodeError::Http`]。
+    #[cfg(feature = "http")]
+    pub fn from_url<Q: AsRef<Path>>(url: &str, output_dir: Q) -> Result<Self, DecodeError> {
+        let response = ureq::get(url)
+            .call()
+            .map_err(|e| DecodeError::Http(Box::new(e)))?;
+        let temp = TempFile::create(&std::env::temp_dir())?;
+        let mut writer = BufWriter::new(temp.file()?);
+        let downloaded = io::copy(&mut response.into_body().into_reader(), &mut writer)?;
+        writer.flush()?;
+        drop(writer);
+
+        let mut extractor = Self::new(&temp.path, output_dir);
+        extractor.download = Some((Arc::new(temp), downloaded));
+        Ok(extractor)
     }
 
     /// 设置读缓冲区大小 (字节)
@@ -45,154 +414,5411 @@ impl ZipExtractor {
         self
     }
 
+    /// 写出每个文件后把数据刷到磁盘并通知内核丢弃其页缓存（需要 `fadvise` feature，默认关闭）
+    ///
+    /// 解压超大归档且短期内不会再读取输出时，避免把系统中其他程序的缓存挤出去。
+    /// 每个文件多一次 `fdatasync`，通常会让解压变慢，只是一个内存占用方面的调节项。
+    /// 目前只在 Linux 上生效，其他平台忽略；通知失败不影响解压结果。
+    #[cfg(feature = "fadvise")]
+    pub fn drop_page_cache(mut self, enabled: bool) -> Self {
+        self.drop_page_cache = enabled;
+        self
+    }
+
+    /// 把每个文件重新压缩为 gzip 写出，输出路径追加 `.gz`（需要 `gzip` feature，默认关闭）
+    ///
+    /// `.gz` 在名称转换、小写化与前缀路由之后追加，覆盖策略、冲突回调与条目回调看到的
+    /// 都是最终的 `.gz` 路径；目录条目不受影响。报告中的 `bytes_written` 仍是解压后的字节数。
+    /// 输出文件的大小与 CRC 不再对应归档条目，因此不要对这样的输出使用
+    /// [`verify_extracted`](Self::verify_extracted)。
+    #[cfg(feature = "gzip")]
+    pub fn gzip_output(mut self, enabled: bool) -> Self {
+        self.gzip_output = enabled;
+        self
+    }
+
     /// 设置工作线程数
     pub fn worker_threads(mut self, count: usize) -> Self {
         self.worker_threads = count;
         self
     }
 
-    /// 执行解压操作（返回解压耗时）
-    pub fn extract(&self) -> Result<f64, ZipError> {
+    /// 严格按中央目录顺序、在调用线程上逐个解压
+    ///
+    /// 与 `worker_threads` 的自动选择无关：开启后无论条目多少都不会创建线程池，
+    /// 写文件等副作用的顺序因此是确定的（例如重名条目总是后出现的生效）。
+    pub fn preserve_order(mut self, enabled: bool) -> Self {
+        self.preserve_order = enabled;
+        self
+    }
+
+    /// 强制单线程解压，等同于 `preserve_order(true)`
+    ///
+    /// 所有条目都在调用线程上处理，保证不会创建 rayon 线程池，
+    /// 适合小归档或限制线程数的运行环境。
+    pub fn sequential(self) -> Self {
+        self.preserve_order(true)
+    }
+
+    /// 以内存映射方式读取输入文件（映射失败时回退为普通文件句柄）
+    ///
+    /// 对自定义数据源无效。
+    pub fn mmap(mut self, enabled: bool) -> Self {
+        self.mmap = enabled;
+        self
+    }
+
+    /// 设置条目名大小写/Unicode 规范化冲突的处理方式
+    pub fn case_collisions(mut self, policy: CollisionPolicy) -> Self {
+        self.collisions = policy;
+        self
+    }
+
+    /// 严格路径检查：条目名含 `..` 时返回 [`DecodeError::UnsafePath`]，而不是静默剔除该部分
+    ///
+    /// 检查在写出任何文件之前完成。
+    pub fn strict_paths(mut self, enabled: bool) -> Self {
+        self.strict_paths = enabled;
+        self
+    }
+
+    /// 设置绝对路径条目的处理方式（默认去掉前缀后解压到输出目录内）
+    ///
+    /// 检查在写出任何文件之前完成，作用于名称转换之后的条目名。
+    pub fn absolute_paths(mut self, policy: AbsolutePathPolicy) -> Self {
+        self.absolute_paths = policy;
+        self
+    }
+
+    /// 设置目标文件已存在时的处理方式
+    pub fn overwrite(mut self, policy: OverwritePolicy) -> Self {
+        self.overwrite = policy;
+        self
+    }
+
+    /// 设置目标文件已存在时的回调，参数为已存在的输出路径与条目信息，由回调逐个决定处理方式
+    ///
+    /// 设置后代替 [`overwrite`](Self::overwrite) 策略，适合合并到已有目录或交互式解压。
+    /// 只对文件条目调用，并行解压时在工作线程中调用。
+    pub fn on_conflict<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Path, &EntryInfo) -> ConflictResolution + Send + Sync + 'static,
+    {
+        self.on_conflict = Some(Arc::new(callback));
+        self
+    }
+
+    /// 设置输出文件的打开方式（默认截断）
+    ///
+    /// 只在覆盖策略为 [`OverwritePolicy::Overwrite`]、[`OverwritePolicy::IfNewer`]
+    /// 或 [`OverwritePolicy::IfDifferent`] 时生效；其他策略下输出文件总是新建的。
+    pub fn output_mode(mut self, mode: OutputMode) -> Self {
+        self.output_mode = mode;
+        self
+    }
+
+    /// 是否校验每个条目的 CRC32（默认开启）
+    ///
+    /// 校验基于拷贝过程中实际读出的数据，与本地头中声明的大小无关，
+    /// 因此对使用数据描述符（大小记为 0）的条目同样有效。
+    pub fn verify_crc(mut self, enabled: bool) -> Self {
+        self.verify_crc = enabled;
+        self
+    }
+
+    /// CRC 不匹配时不再中止，而是把损坏的条目记入 [`ExtractionReport::corrupt`] 后继续
+    ///
+    /// 默认关闭。适合数据恢复场景：一次解压即可得知损坏的全部范围。损坏条目的数据仍会写出
+    /// （开启原子写入时除外），但不计入 `files_extracted`，也不写入检查点。
+    /// 只影响 CRC 校验，其他错误仍会中止解压；未开启 `verify_crc` 时不起作用。
+    pub fn collect_crc_errors(mut self, enabled: bool) -> Self {
+        self.collect_crc_errors = enabled;
+        self
+    }
+
+    /// 限制实际解压出的总字节数（防御 zip 炸弹）
+    ///
+    /// 按拷贝时实际写出的字节计数，不信任归档中声明的大小。
+    pub fn max_total_size(mut self, bytes: u64) -> Self {
+        self.max_total_size = Some(bytes);
+        self
+    }
+
+    /// 限制单个条目的压缩比（实际解压字节数 / 压缩大小）
+    pub fn max_compression_ratio(mut self, ratio: f64) -> Self {
+        self.max_compression_ratio = Some(ratio);
+        self
+    }
+
+    /// 压缩比（实际解压字节数 / 压缩大小）超过 `ratio` 的条目输出警告并记入报告的
+    /// `suspicious_entries`，不中止解压
+    ///
+    /// 用于在 [`max_compression_ratio`](Self::max_compression_ratio) 之下提前发现
+    /// 可能的 zip 炸弹试探，通常设为上限的一部分。
+    pub fn warn_compression_ratio(mut self, ratio: f64) -> Self {
+        self.warn_compression_ratio = Some(ratio);
+        self
+    }
+
+    /// 限制归档的条目数（防御由大量小文件构成的 zip 炸弹）
+    ///
+    /// 按中央目录中的条目总数（含目录条目）判断，在写出任何文件之前失败。
+    pub fn max_entries(mut self, count: usize) -> Self {
+        self.max_entries = Some(count);
+        self
+    }
+
+    /// Windows 下为超过 MAX_PATH (260) 的输出路径自动加 `\\?\` 长路径前缀
+    ///
+    /// 未开启时超长路径返回 [`DecodeError::PathTooLong`] 并指明条目；其他平台上无效果。
+    pub fn long_paths(mut self, enabled: bool) -> Self {
+        self.long_paths = enabled;
+        self
+    }
+
+    /// 记录每个文件条目的解压耗时，在报告的 `slowest_entries` 中保留最慢的 `count` 个
+    ///
+    /// 用于排查个别条目异常缓慢的归档。耗时包含解压与写盘；每个工作线程各自记录，
+    /// 结束时统一排序，不引入额外的锁。默认不记录。
+    pub fn track_slowest(mut self, count: usize) -> Self {
+        self.slowest_entries = count;
+        self
+    }
+
+    /// 把条目放到 `output_dir/subdir/` 下，便于把多个归档并排解压到同一个根目录而互不冲突
+    ///
+    /// `subdir` 必须是安全的相对路径（可含多级，如 `"a/b"`），不能为空、是绝对路径或含 `..`，
+    /// 否则解压时返回 [`DecodeError::UnsafePath`]。按前缀路由到其他目录的条目不受影响。
+    pub fn subdir(mut self, subdir: &str) -> Self {
+        self.subdir = Some(subdir.to_string());
+        self
+    }
+
+    /// 把顶层目录为 `prefix` 的条目解压到 `dir`（去掉该顶层目录）
+    ///
+    /// 例如 `route_prefix("images", "/srv/img")` 会把 `images/a/b.png` 写到
+    /// `/srv/img/a/b.png`。前缀按清理后的第一个路径组件精确匹配，首尾的 `/` 会被忽略。
+    pub fn route_prefix<S: Into<String>, P: AsRef<Path>>(mut self, prefix: S, dir: P) -> Self {
+        let prefix = prefix.into().trim_matches('/').to_string();
+        self.routes.insert(prefix, dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// 设置了前缀路由时，跳过不匹配任何前缀的条目（记为 [`SkipReason::Unrouted`]）
+    ///
+    /// 默认关闭，此时这些条目照常解压到输出目录。
+    pub fn skip_unrouted(mut self, enabled: bool) -> Self {
+        self.skip_unrouted = enabled;
+        self
+    }
+
+    /// 在内置路径清理之前改写条目名，返回 `None` 表示跳过该条目（记为 [`SkipReason::NameRejected`]）
+    ///
+    /// 严格路径检查、`..` 剔除、冲突检测与前缀路由都作用于转换后的名称；
+    /// 报告与事件中仍使用归档中的原始条目名。
+    pub fn name_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.name_transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// 跳过 macOS 打包时附带的元数据：`__MACOSX/` 目录、`.DS_Store` 与 AppleDouble `._*` 文件
+    ///
+    /// 默认关闭；被跳过的条目记为 [`SkipReason::MacMetadata`]。
+    pub fn skip_mac_metadata(mut self, enabled: bool) -> Self {
+        self.skip_mac_metadata = enabled;
+        self
+    }
+
+    /// 是否把符号链接条目写成普通文件，文件内容为链接目标的路径文本（默认关闭）
+    ///
+    /// 默认情况下符号链接条目（Unix 权限位为 `S_IFLNK`）被跳过并记为 [`SkipReason::Symlink`]，
+    /// 不会在输出目录中出现指向任意位置的链接，也不会留下看起来正常、实为路径文本的文件。
+    /// 开启后得到的是一个普通文件而不是链接，只适合需要查看链接目标的场景。
+    pub fn symlinks_as_files(mut self, enabled: bool) -> Self {
+        self.symlinks_as_files = enabled;
+        self
+    }
+
+    /// 先把每个文件完整写入临时文件，再重命名到目标位置
+    ///
+    /// 其他进程永远不会看到写了一半的文件，中断时目标位置要么是旧文件要么不存在。
+    /// 只对 [`OutputMode::Truncate`] 生效；追加或按偏移写入时仍直接写目标文件。
+    pub fn atomic_writes(mut self, enabled: bool) -> Self {
+        self.atomic_writes = enabled;
+        self
+    }
+
+    /// 设置 [`atomic_writes`](Self::atomic_writes) 使用的临时文件目录，默认为输出目录
+    ///
+    /// 目录不存在时会被创建。重命名只有在同一文件系统内才是原子的，
+    /// 临时目录与输出目录不在同一文件系统时会记录警告，并退化为复制后删除。
+    pub fn temp_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.temp_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// 拷贝数据的同时按 `algorithm` 计算摘要，与外部清单 `expected` 比较
+    ///
+    /// `expected` 为 条目名 -> 十六进制摘要。用于对照可信清单校验，而不仅是归档自带的 CRC32；不需要再次读取输出文件。
+    /// 十六进制不区分大小写，不在清单中的条目不做此校验。不一致时返回
+    /// [`DecodeError::DigestMismatch`]。同样作用于 [`test_archive`](Self::test_archive)。
+    pub fn verify_with(
+        mut self,
+        algorithm: HashAlgorithm,
+        expected: HashMap<String, String>,
+    ) -> Self {
+        let expected = expected
+            .into_iter()
+            .map(|(name, digest)| (name, digest.to_ascii_lowercase()))
+            .collect();
+        self.manifest = Some((algorithm, Arc::new(expected)));
+        self
+    }
+
+    /// 设置 [`extract_to_memory`](Self::extract_to_memory) 的内存阈值 (字节，默认 16MB)
+    pub fn spill_threshold(mut self, bytes: u64) -> Self {
+        self.spill_threshold = bytes;
+        self
+    }
+
+    /// 把输出路径全部转为小写，使解压结果在大小写敏感与不敏感的文件系统上表现一致
+    ///
+    /// 在名称转换之后、路径检查与清理之前进行，
+    /// 因此严格路径检查与冲突检测都作用于小写后的路径。
+    /// 转换后重名的文件即使在 [`CollisionPolicy::Allow`] 下也不会互相覆盖，
+    /// 后出现的条目记为 [`SkipReason::CaseCollision`]。
+    pub fn lowercase_names(mut self, enabled: bool) -> Self {
+        self.lowercase_names = enabled;
+        self
+    }
+
+    /// 只写出扩展名在白名单中的文件
+    ///
+    /// 其余文件条目记为 [`SkipReason::DisallowedExtension`]，没有扩展名的文件同样被拒绝。
+    /// 扩展名大小写不敏感，可带前导点；按清理后的输出路径判断，目录条目不受影响。适合解压不可信的上传文件，如只允许 `["txt", "srt", "ass"]`。
+    pub fn allowed_extensions(mut self, extensions: &[&str]) -> Self {
+        self.allowed_extensions = Some(
+            extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect(),
+        );
+        self
+    }
+
+    /// 跳过解压后小于 `bytes` 的文件条目，记为 [`SkipReason::SizeOutOfRange`]
+    ///
+    /// 大小取自中央目录，在解压之前判断；目录条目不受影响。
+    pub fn min_entry_size(mut self, bytes: u64) -> Self {
+        self.min_entry_size = Some(bytes);
+        self
+    }
+
+    /// 跳过解压后大于 `bytes` 的文件条目，记为 [`SkipReason::SizeOutOfRange`]
+    ///
+    /// 与 [`min_entry_size`](Self::min_entry_size) 相同按中央目录中声明的大小判断。
+    /// 这只是筛选，不能代替 [`max_total_size`](Self::max_total_size) 等按实际字节计数的防护。
+    pub fn max_entry_size(mut self, bytes: u64) -> Self {
+        self.max_entry_size = Some(bytes);
+        self
+    }
+
+    /// 只写出按内容检测出的 MIME 类型在列表中的文件，如 `["image/*", "application/pdf"]`
+    ///
+    /// 类型由条目解压后的前 8KB 数据判断（基于 `infer`），不看扩展名，因此适合扩展名缺失或
+    /// 不可信的归档；无法识别的内容（包括纯文本）视为不匹配。其余文件条目记为
+    /// [`SkipReason::ContentType`]，目录条目不受影响。
+    ///
+    /// 与按名称过滤不同，扫描阶段需要为每个候选条目额外解压一小段数据，大归档上开销明显，
+    /// 能用扩展名过滤时应优先使用 [`allowed_extensions`](Self::allowed_extensions)。
+    pub fn content_types(mut self, types: &[&str]) -> Self {
+        self.content_types = Some(
+            types
+                .iter()
+                .map(|t| t.trim_end_matches('*').to_lowercase())
+                .collect(),
+        );
+        self
+    }
+
+    /// 只创建归档中的目录结构（包括文件所在的父目录），不写出任何文件内容
+    ///
+    /// 不会解压任何数据，适合预先创建目录布局或快速查看结构。
+    /// 报告中 `files_extracted` 为 0，`dirs_created` 为归档中的目录条目数。
+    pub fn dirs_only(mut self, enabled: bool) -> Self {
+        self.dirs_only = enabled;
+        self
+    }
+
+    /// 在每个写出的文件旁另写一个 `<文件名>.meta.json`，记录条目在归档中的元数据（默认关闭）
+    ///
+    /// 包含条目名、大小、压缩后大小、CRC32、修改时间（zip 中不带时区的本地时间，未记录时为 `null`）、
+    /// 条目注释与压缩方式，用于在解压时保留来源信息。这些文件不计入报告中的文件数与字节数，
+    /// 也不受总大小上限约束，已存在时直接覆盖。
+    pub fn metadata_sidecars(mut self, enabled: bool) -> Self {
+        self.metadata_sidecars = enabled;
+        self
+    }
+
+    /// 把成功解压的条目记录到检查点文件，下次解压时从中断处继续
+    ///
+    /// 每完成一个条目追加一行 `索引 CRC32`。再次解压时，检查点中已记录且 CRC 与归档一致的条目
+    /// 会被跳过（记为 [`SkipReason::Resumed`]）；开启 `verify_crc` 时还会重新计算输出文件的
+    /// CRC，不一致或文件缺失的条目照常重新解压。检查点文件不会被自动删除。
+    pub fn checkpoint<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.checkpoint = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// 设置按字节的进度回调（并行解压时会在工作线程中调用）
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(Progress) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// 设置开始处理每个条目（文件或目录）时的回调，此时尚未写出任何数据
+    ///
+    /// 并行解压时在工作线程中调用。之后被跳过的条目（如已存在）同样会触发该回调。
+    pub fn on_entry_start<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&EntryInfo) + Send + Sync + 'static,
+    {
+        self.entry_start = Some(Arc::new(callback));
+        self
+    }
+
+    /// 设置每个条目写出后的回调，参数为条目信息与实际输出路径
+    ///
+    /// 适合扫描、建立索引或设置扩展属性等后处理。并行解压时在工作线程中调用；
+    /// 跳过或失败的条目不会触发。
+    pub fn on_entry_done<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&EntryInfo, &Path) + Send + Sync + 'static,
+    {
+        self.entry_done = Some(Arc::new(callback));
+        self
+    }
+
+    /// 按条目决定缓冲区大小，返回 `(读块大小, 写缓冲大小)`，均按字节计
+    ///
+    /// 读块是每次从解压流读取的长度（默认 64KB），写缓冲是输出文件的 `BufWriter` 容量
+    /// （默认为 [`write_buffer_size`](Self::write_buffer_size)）。大小不一的归档可以给小文件
+    /// 小缓冲、给大文件大缓冲以降低内存占用；返回 0 时按 1 处理。只影响写出到磁盘的条目。
+    pub fn buffer_sizer<F>(mut self, sizer: F) -> Self
+    where
+        F: Fn(&EntryInfo) -> (usize, usize) + Send + Sync + 'static,
+    {
+        self.buffer_sizer = Some(Arc::new(sizer));
+        self
+    }
+
+    /// 按实测吞吐量自动选择缓冲区大小（默认关闭）
+    ///
+    /// 前若干个文件轮流使用配置的大小（读块 64KB、写缓冲为 [`write_buffer_size`](Self::write_buffer_size)）
+    /// 及其 1/4 与 4 倍，每组试用 3 个文件后，之后的文件都使用吞吐量最高的一组，
+    /// 选出的大小记入报告的 `buffer_sizes`。文件很小或很少时测量噪声较大，
+    /// 适合由大量较大文件组成的归档。设置了 [`buffer_sizer`](Self::buffer_sizer) 时不生效。
+    pub fn adaptive_buffers(mut self, enabled: bool) -> Self {
+        self.adaptive_buffers = enabled;
+        self
+    }
+
+    /// 设置进度回调的字节间隔（0 表示每次写入都回调）
+    pub fn progress_interval(mut self, bytes: u64) -> Self {
+        self.progress_interval = bytes;
+        self
+    }
+
+    /// 在后台线程中解压，并以迭代器的形式逐个产出事件
+    ///
+    /// 依次产生 `Started`、每个条目的 `EntryExtracted`/`EntrySkipped`，
+    /// 最后以 `Finished(report)` 或 `Failed(error)` 结束。通道有界，
+    /// 消费过慢时解压线程会等待；提前丢弃迭代器不会中断解压。
+    pub fn into_events(mut self) -> impl Iterator<Item = ExtractEvent> {
+        let (tx, rx) = mpsc::sync_channel(256);
+        self.events = Some(tx.clone());
+        thread::spawn(move || {
+            let _ = tx.send(ExtractEvent::Started);
+            let last = match self.extract() {
+                Ok(report) => ExtractEvent::Finished(report),
+                Err(e) => ExtractEvent::Failed(e),
+            };
+            // 先释放解压器持有的发送端，保证最后一个事件之后迭代器立即结束
+            drop(self);
+            let _ = tx.send(last);
+        });
+        rx.into_iter()
+    }
+
+    /// 执行解压操作（返回统计结果）
+    pub fn extract(&self) -> Result<ExtractionReport, DecodeError> {
+        self.run(None).map_err(|partial| partial.error)
+    }
+
+    /// 与 [`extract`](Self::extract) 相同，但失败时同时返回失败前已完成部分的统计
+    ///
+    /// 调用方无需重新扫描输出目录就能知道中止前写出和跳过了哪些条目。
+    /// 顺序解压在第一个错误处中止；并行解压中某个条目出错后，其余工作线程在各自的当前条目完成后停止，
+    /// 返回第一个错误。工作线程 panic 时，其余条目完成后才返回。
+    pub fn extract_partial(&self) -> Result<ExtractionReport, Box<PartialExtraction>> {
+        self.run(None)
+    }
+
+    /// 只解压中央目录中指定位置的条目（例如界面上勾选的文件）
+    ///
+    /// 重复的索引只解压一次，按给定顺序处理。任何索引越界时返回
+    /// [`DecodeError::IndexOutOfRange`] 并列出全部越界的索引，不写出任何文件。
+    /// 其余检查（路径、冲突、过滤等）与 [`extract`](Self::extract) 相同。
+    pub fn extract_indices(&self, indices: &[usize]) -> Result<ExtractionReport, DecodeError> {
+        self.run(Some(indices)).map_err(|partial| partial.error)
+    }
+
+    /// 解压全部条目或 `selection` 指定的条目
+    fn run(&self, selection: Option<&[usize]>) -> Result<ExtractionReport, Box<PartialExtraction>> {
         let start_time = Instant::now();
+        let mut report = ExtractionReport {
+            downloaded_bytes: self.download.as_ref().map_or(0, |(_, bytes)| *bytes),
+            ..Default::default()
+        };
+        let result = self.run_into(selection, &mut report);
+        report.keep_slowest(self.slowest_entries);
+        report.duration = start_time.elapsed();
+        match result {
+            Ok(()) => Ok(report),
+            Err(error) => Err(Box::new(PartialExtraction { error, report })),
+        }
+    }
 
-        // 打开 ZIP 文件并使用大缓冲区
-        let file = File::open(&self.zip_path)?;
-        let reader = BufReader::with_capacity(self.read_buffer_size, file);
-        let mut archive = ZipArchive::new(reader)?;
+    /// [`run`](Self::run) 的主体，出错时 `report` 中保留已完成的部分
+    fn run_into(
+        &self,
+        selection: Option<&[usize]>,
+        report: &mut ExtractionReport,
+    ) -> Result<(), DecodeError> {
+        let RunState {
+            source,
+            mut archive,
+            entries,
+            ctx,
+            bytes_total,
+        } = self.start(selection, report)?;
 
         // 确定最佳线程数
-        let num_files = archive.len();
+        let num_files = entries.len();
         let num_threads = match self.worker_threads {
             0 => (num_files / 20).clamp(1, num_cpus::get()), // 每20个文件一个线程
             n => n.min(num_files),
         };
 
-        if num_threads > 1 {
-            self.extract_parallel(&mut archive, num_threads)?;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "extract",
+            entries = num_files,
+            bytes_total,
+            threads = num_threads
+        )
+        .entered();
+        #[cfg(not(feature = "tracing"))]
+        let _ = bytes_total;
+        let result = if num_threads > 1 && !self.preserve_order {
+            self.extract_parallel(source.as_ref(), &entries, num_threads, &ctx, report)
         } else {
-            self.extract_sequential(&mut archive)?;
-        }
+            report.threads_used = 1;
+            self.extract_sequential(&mut archive, &entries, &ctx, report)
+        };
+        report.buffer_sizes = ctx.chosen_buffer_sizes();
+        result
+    }
 
-        let duration = start_time.elapsed().as_secs_f64();
-        Ok(duration)
+    /// 逐个解压条目，每个条目产出一个结果：成功时为条目信息，失败时为该条目的错误
+    ///
+    /// 总是在调用线程上按归档顺序处理。某个条目失败后可以继续迭代处理其余条目，
+    /// 也可以直接停止（丢弃迭代器），未处理的条目不会写出。打开或扫描归档失败时
+    /// 只产出这一个错误。扫描阶段被跳过的条目不会产出，处理中被跳过的（如目标已存在）
+    /// 产出 `Ok`，可通过 [`TryEntries::into_report`] 查看。
+    pub fn try_entries(&self) -> TryEntries<'_> {
+        let start_time = Instant::now();
+        let mut report = ExtractionReport {
+            downloaded_bytes: self.download.as_ref().map_or(0, |(_, bytes)| *bytes),
+            ..Default::default()
+        };
+        report.threads_used = 1;
+        let state = self.start(None, &mut report);
+        let (state, pending) = match state {
+            Ok(state) => (Some(state), None),
+            Err(e) => (None, Some(e)),
+        };
+        TryEntries {
+            extractor: self,
+            state,
+            pending,
+            next: 0,
+            report,
+            start_time,
+        }
     }
 
-    /// 顺序解压（单线程）
-    fn extract_sequential(
+    /// 打开归档、扫描条目并准备解压上下文
+    fn start(
         &self,
-        archive: &mut ZipArchive<BufReader<File>>,
-    ) -> Result<(), ZipError> {
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)?;
-            let out_path = self.output_dir.join(file.sanitized_name());
+        selection: Option<&[usize]>,
+        report: &mut ExtractionReport,
+    ) -> Result<RunState, DecodeError> {
+        // 打开 ZIP 文件并使用大缓冲区
+        let source = self.source();
+        let mut archive = {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!("open").entered();
+            open_archive(source.as_ref())?
+        };
 
-            if file.is_dir() {
-                std::fs::create_dir_all(&out_path)?;
-            } else {
-                self.extract_file(&mut file, &out_path)?;
+        let candidates: Vec<usize> = match selection {
+            Some(indices) => {
+                let len = archive.len();
+                let out_of_range: Vec<usize> =
+                    indices.iter().copied().filter(|&i| i >= len).collect();
+                if !out_of_range.is_empty() {
+                    return Err(DecodeError::IndexOutOfRange {
+                        indices: out_of_range,
+                        len,
+                    });
+                }
+                let mut seen = HashSet::new();
+                indices
+                    .iter()
+                    .copied()
+                    .filter(|&i| seen.insert(i))
+                    .collect()
             }
+            None => (0..archive.len()).collect(),
+        };
+
+        let completed = match &self.checkpoint {
+            Some(path) => load_checkpoint(path)?,
+            None => HashSet::new(),
+        };
+        let (entries, bytes_total) = {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!("scan", candidates = candidates.len()).entered();
+            self.plan(&mut archive, &candidates, &completed, report)?
+        };
+
+        let mut ctx = ExtractContext::new(self.progress.as_ref().map(|callback| {
+            ProgressTracker::new(Arc::clone(callback), bytes_total, self.progress_interval)
+        }));
+        if let Some(path) = &self.checkpoint {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            ctx.checkpoint = Some(Mutex::new(file));
+        }
+        if self.atomic_writes && self.output_mode == OutputMode::Truncate {
+            ctx.temp_dir = Some(self.prepare_temp_dir()?);
+        }
+        if self.adaptive_buffers && self.buffer_sizer.is_none() {
+            ctx.adaptive = Some(Mutex::new(AdaptiveBuffers::new((
+                COPY_BUFFER_SIZE,
+                self.write_buffer_size,
+            ))));
+        }
+        Ok(RunState {
+            source,
+            archive,
+            entries,
+            ctx,
+            bytes_total,
+        })
+    }
+
+    /// 校验归档完整性而不写出任何文件（类似 `unzip -t`）
+    ///
+    /// 逐个把条目解压到 `io::sink()` 并校验 CRC（不受 `verify_crc` 影响），
+    /// 遇到第一个损坏的条目即返回 [`DecodeError::CrcMismatch`] 或 [`DecodeError::CorruptEntry`]。
+    pub fn test_archive(&self) -> Result<(), DecodeError> {
+        let mut archive = open_archive(self.source().as_ref())?;
+        let ctx = ExtractContext::new(None);
+
+        for i in 0..archive.len() {
+            self.test_entry(&mut archive.by_index(i)?, &ctx)?;
         }
         Ok(())
     }
 
-    /// 并行解压（多线程）
-    fn extract_parallel(
+    /// 与 [`test_archive`](Self::test_archive) 相同，但用 `threads` 个线程（0 为按 CPU 数）
+    /// 并行校验，并返回所有损坏的条目而不只是第一个
+    ///
+    /// 每个线程持有独立的归档句柄。返回的 `(条目名, 错误)` 按归档顺序排列，为空表示归档完好；
+    /// 无法打开归档时返回外层错误。
+    pub fn test_archive_parallel(
         &self,
-        archive: &mut ZipArchive<BufReader<File>>,
-        num_threads: usize,
-    ) -> Result<(), ZipError> {
-        // 创建线程池
+        threads: usize,
+    ) -> Result<Vec<(String, DecodeError)>, DecodeError> {
+        let source = self.source();
+        let len = open_archive(source.as_ref())?.len();
+        let num_threads = match threads {
+            0 => num_cpus::get(),
+            n => n,
+        }
+        .clamp(1, len.max(1));
         let pool = rayon::ThreadPoolBuilder::new()
             .num_threads(num_threads)
             .build()
-            .unwrap();
-
-        // 创建文件索引列表
-        let file_indices: Vec<usize> = (0..archive.len()).collect();
-
-        // 共享 ZIP 文件的原子引用计数器
-        let archive_mutex = Arc::new(Mutex::new(archive));
+            .map_err(io::Error::other)?;
 
+        let ctx = ExtractContext::new(None);
+        let failures = Mutex::new(Vec::new());
+        let open_error = Mutex::new(None);
+        let indices: Vec<usize> = (0..len).collect();
         pool.scope(|s| {
-            for chunk in file_indices.chunks(file_indices.len() / num_threads + 1) {
-                let archive_ref = Arc::clone(&archive_mutex);
-                let extractor = self; // 借用 self
-
+            for chunk in indices.chunks(len / num_threads + 1) {
+                let (ctx, failures, open_error) = (&ctx, &failures, &open_error);
+                let source = source.as_ref();
                 s.spawn(move |_| {
+                    let mut archive = match open_archive(source) {
+                        Ok(archive) => archive,
+                        Err(e) => {
+                            *open_error.lock().unwrap_or_else(PoisonError::into_inner) = Some(e);
+                            return;
+                        }
+                    };
                     for &index in chunk {
-                        let mut archive = archive_ref.lock().unwrap();
-                        let mut file = match archive.by_index(index) {
-                            Ok(f) => f,
-                            Err(e) => {
-                                eprintln!("Error accessing file {}: {}", index, e);
-                                continue;
-                            }
+                        let result = match archive.by_index(index) {
+                            Ok(mut file) => self.test_entry(&mut file, ctx),
+                            Err(e) => Err(e.into()),
                         };
-
-                        let out_path = extractor.output_dir.join(file.sanitized_name());
-
-                        if file.is_dir() {
-                            if let Err(e) = std::fs::create_dir_all(&out_path) {
-                                eprintln!("Error creating directory {:?}: {}", out_path, e);
-                            }
-                        } else {
-                            if let Err(e) = extractor.extract_file(&mut file, &out_path) {
-                                eprintln!("Error extracting file {:?}: {}", out_path, e);
-                            }
+                        if let Err(e) = result {
+                            let name = archive
+                                .by_index_raw(index)
+                                .map(|file| file.name().to_string())
+                                .unwrap_or_else(|_| format!("#{}", index));
+                            failures
+                                .lock()
+                                .unwrap_or_else(PoisonError::into_inner)
+                                .push((index, name, e));
                         }
                     }
                 });
             }
         });
 
-        Ok(())
+        if let Some(e) = open_error
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+        {
+            return Err(e);
+        }
+        let mut failures = failures
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        failures.sort_by_key(|(index, ..)| *index);
+        Ok(failures
+            .into_iter()
+            .map(|(_, name, error)| (name, error))
+            .collect())
     }
 
-    /// 提取单个文件（核心提取逻辑）
-    fn extract_file<R: Read>(&self, reader: &mut R, output_path: &Path) -> Result<(), io::Error> {
-        // 确保父目录存在
-        if let Some(parent) = output_path.parent() {
-            if !parent.exists() {
-                std::fs::create_dir_all(parent)?;
-            }
+    /// 把单个文件条目解压到 `io::sink()` 并校验 CRC，解压失败记为 [`DecodeError::CorruptEntry`]
+    fn test_entry(&self, file: &mut ZipFile<'_>, ctx: &ExtractContext) -> Result<(), DecodeError> {
+        if file.is_dir() {
+            return Ok(());
         }
+        match self.copy_entry(file, &mut io::sink(), ctx, true) {
+            Ok(_) => Ok(()),
+            Err(DecodeError::Io(source)) => Err(DecodeError::CorruptEntry {
+                entry: file.name().to_string(),
+                source,
+            }),
+            Err(e) => Err(e),
+        }
+    }
 
-        // 使用缓冲写入器
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(output_path)?;
+    /// 检查之前解压到 `output_dir` 的文件是否仍与归档一致，返回所有差异
+    ///
+    /// 按与 [`extract`](Self::extract) 相同的规则（名称转换、过滤、路由等）确定输出路径，
+    /// 其中位于本解压器输出目录下的路径改为相对 `output_dir`。先比较大小，大小一致时
+    /// 重新读取文件计算 CRC32 并与中央目录比较，不需要解压归档数据。目录条目不检查。
+    pub fn verify_extracted<P: AsRef<Path>>(
+        &self,
+        output_dir: P,
+    ) -> Result<Vec<Mismatch>, DecodeError> {
+        let output_dir = output_dir.as_ref();
+        let mut archive = open_archive(self.source().as_ref())?;
+        let candidates: Vec<usize> = (0..archive.len()).collect();
+        let (entries, _) = self.plan(
+            &mut archive,
+            &candidates,
+            &HashSet::new(),
+            &mut ExtractionReport::default(),
+        )?;
 
-        let mut writer = BufWriter::with_capacity(self.write_buffer_size, file);
+        let mut mismatches = Vec::new();
+        for entry in entries {
+            let file = archive.by_index_raw(entry.index)?;
+            if file.is_dir() {
+                continue;
+            }
+            let path = match entry.out_path.strip_prefix(&self.output_dir) {
+                Ok(relative) => output_dir.join(relative),
+                Err(_) => entry.out_path.clone(),
+            };
+            let path = self.platform_path(file.name(), path)?;
 
-        // 使用大缓冲区拷贝数据
-        let mut buffer = vec![0u8; 64 * 1024]; // 64KB 拷贝缓冲区
-        while let Ok(n) = reader.read(&mut buffer) {
-            if n == 0 {
-                break;
+            let kind = match std::fs::metadata(&path) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Some(MismatchKind::Missing),
+                Err(e) => return Err(e.into()),
+                Ok(metadata) if metadata.len() != file.size() => Some(MismatchKind::SizeDiffers {
+                    expected: file.size(),
+                    actual: metadata.len(),
+                }),
+                Ok(_) => {
+                    let actual = file_crc(&path)?;
+                    (actual != file.crc32()).then_some(MismatchKind::CrcDiffers {
+                        expected: file.crc32(),
+                        actual,
+                    })
+                }
+            };
+            if let Some(kind) = kind {
+                mismatches.push(Mismatch {
+                    entry: file.name().to_string(),
+                    path,
+                    kind,
+                });
             }
-            writer.write_all(&buffer[..n])?;
         }
+        Ok(mismatches)
+    }
 
-        writer.flush()?;
-        Ok(())
+    /// 读取归档的格式信息：条目数、所需版本、是否 ZIP64、是否含加密条目
+    ///
+    /// 只读取中央目录，不解压任何数据。错误类型与其他方法一样是 [`DecodeError`] 而不是
+    /// `ZipError`：除了包装在 [`DecodeError::Zip`] 中的 zip 错误，还需要报告输入不是 ZIP
+    /// （[`DecodeError::NotAnArchive`]）、归档被截断（[`DecodeError::TruncatedArchive`]）
+    /// 以及直接读取中央目录时的 IO 错误（[`DecodeError::Io`]）。
+    pub fn info(&self) -> Result<ArchiveInfo, DecodeError> {
+        let source = self.source();
+        let mut archive = open_archive(source.as_ref())?;
+        let mut reader = source.open()?;
+
+        let mut info = ArchiveInfo {
+            entries: archive.len(),
+            version_needed: 0,
+            zip64: match EndOfCentralDirectory::find(&mut reader)? {
+                Some(eocd) => eocd.has_zip64_locator(&mut reader)?,
+                None => false,
+            },
+            encrypted: false,
+        };
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i)?;
+            let fields = CentralHeaderFields::read_at(&mut reader, file.central_header_start())?;
+            info.version_needed = info.version_needed.max(fields.version_needed);
+            info.encrypted |= fields.is_encrypted();
+            info.zip64 |= eocd::has_zip64_extra(file.extra_data());
+        }
+        Ok(info)
     }
-}
 
-// 为 zip::read::ZipFile 添加 sanitized_name 方法
-trait SafeName {
-    fn sanitized_name(&self) -> PathBuf;
-}
+    /// 全部条目声明的解压后总字节数（只读取中央目录）
+    ///
+    /// 超过 4GB 的 ZIP64 条目按扩展字段中的 64 位大小计算，而不是头部的 `0xFFFFFFFF` 占位值。
+    pub fn total_uncompressed_size(&self) -> Result<u64, DecodeError> {
+        let mut archive = open_archive(self.source().as_ref())?;
+        let mut total = 0u64;
+        for i in 0..archive.len() {
+            total = total.saturating_add(archive.by_index_raw(i)?.size());
+        }
+        Ok(total)
+    }
 
-impl<'a> SafeName for zip::read::ZipFile<'a> {
-    fn sanitized_name(&self) -> PathBuf {
-        self.name()
-            .split('/')
-            .filter(|s| !s.is_empty() && *s != "..")
-            .fold(PathBuf::new(), |mut path, comp| {
-                path.push(comp);
-                path
-            })
+    /// 粗略估计单线程解压整个归档所需的时间，用于初始化进度条的剩余时间
+    ///
+    /// 只读取中央目录，按每个条目声明的解压后大小与其压缩方法的典型解压速度累加，
+    /// 不包含磁盘写入开销。实际耗时随硬件差异很大，开始解压后应改用实测速度。
+    pub fn estimate_duration(&self) -> Result<Duration, DecodeError> {
+        let mut archive = open_archive(self.source().as_ref())?;
+        let mut seconds = 0.0;
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i)?;
+            seconds += file.size() as f64 / decompress_speed(file.compression()) as f64;
+        }
+        Ok(Duration::from_secs_f64(seconds))
+    }
+
+    /// 按中央目录中的存储顺序返回全部条目名（含目录条目，不做清理与排序）
+    ///
+    /// OOXML、JAR 等格式依赖条目的存储顺序；只读取中央目录，不解压任何数据。
+    /// 与 [`list`](Self::list) 一样返回 [`DecodeError`]，以便区分非 ZIP 输入、截断的归档等
+    /// 打开阶段的错误；zip 本身的错误在 [`DecodeError::Zip`] 中。
+    pub fn entries_in_order(&self) -> Result<Vec<String>, DecodeError> {
+        let mut archive = open_archive(self.source().as_ref())?;
+        (0..archive.len())
+            .map(|i| Ok(archive.by_index_raw(i)?.name().to_string()))
+            .collect()
+    }
+
+    /// 按中央目录顺序列出全部条目的元数据（只读取中央目录）
+    pub fn list(&self) -> Result<Vec<EntryInfo>, DecodeError> {
+        let mut archive = open_archive(self.source().as_ref())?;
+        (0..archive.len())
+            .map(|i| Ok(EntryInfo::from_zip(i, &archive.by_index_raw(i)?)))
+            .collect()
+    }
+
+    /// 按目录结构返回归档内容（名称与大小），便于文件浏览器在解压前展示
+    ///
+    /// 只读取中央目录；这里使用原始条目名，不做名称转换与路径清理。
+    pub fn tree(&self) -> Result<DirNode, DecodeError> {
+        Ok(DirNode::from_entries(&self.list()?))
+    }
+
+    /// 比较本归档（旧）与 `other`（新）的条目，按条目名匹配，CRC32 或大小不同即视为修改
+    ///
+    /// 只读取两个归档的中央目录，不解压任何数据。同名条目出现多次时以第一次为准。
+    pub fn diff(&self, other: &ZipExtractor) -> Result<ArchiveDiff, DecodeError> {
+        let by_name = |entries: Vec<EntryInfo>| {
+            let mut map = BTreeMap::new();
+            for entry in entries {
+                map.entry(entry.name.clone()).or_insert(entry);
+            }
+            map
+        };
+        let old = by_name(self.list()?);
+        let mut new = by_name(other.list()?);
+
+        let mut diff = ArchiveDiff::default();
+        for (name, before) in old {
+            match new.remove(&name) {
+                None => diff.removed.push(before),
+                Some(after) if after.crc32 != before.crc32 || after.size != before.size => {
+                    diff.changed.push((before, after))
+                }
+                Some(_) => {}
+            }
+        }
+        diff.added = new.into_values().collect();
+        Ok(diff)
+    }
+
+    /// 把名为 `name` 的单个条目解压到任意 `writer`（如 stdout、网络连接），返回写出的字节数
+    ///
+    /// 与 [`extract`](Self::extract) 一样执行大小限制、进度回调与 CRC 校验：数据在校验前就已写入
+    /// `writer`，损坏的条目会在末尾返回 [`DecodeError::CrcMismatch`]，调用方应据此丢弃已收到的数据。
+    /// 条目不存在时返回 `ZipError::FileNotFound`。
+    pub fn extract_entry_to_writer<W: Write>(
+        &self,
+        name: &str,
+        writer: &mut W,
+    ) -> Result<u64, DecodeError> {
+        let mut archive = open_archive(self.source().as_ref())?;
+        let mut file = archive.by_name(name)?;
+        self.write_entry(&mut file, writer)
+    }
+
+    /// 把名为 `name` 的单个条目完整读入内存
+    ///
+    /// 按中央目录中声明的解压后大小预先分配 `Vec`，避免读取过程中反复扩容。与
+    /// [`extract_entry_to_writer`](Self::extract_entry_to_writer) 一样执行大小限制、进度回调与
+    /// CRC 校验。预分配最多 64MB（设置了 [`max_total_size`](Self::max_total_size)
+    /// 时也不超过该上限），更大的条目在读取中按需扩容，因此伪造的大小字段不会导致过量分配。
+    /// 实际读出的长度与声明的大小不符时返回 [`DecodeError::EntrySizeMismatch`]。
+    /// 条目不存在时返回 `ZipError::FileNotFound`。
+    pub fn read_entry_to_vec(&self, name: &str) -> Result<Vec<u8>, DecodeError> {
+        let mut archive = open_archive(self.source().as_ref())?;
+        let mut file = archive.by_name(name)?;
+        let declared = file.size();
+        let capacity = self
+            .max_total_size
+            .map_or(declared, |limit| declared.min(limit))
+            .min(MAX_PREALLOC as u64);
+        let mut data = Vec::with_capacity(capacity as usize);
+        let actual = self.write_entry(&mut file, &mut data)?;
+        if actual != declared {
+            return Err(DecodeError::EntrySizeMismatch {
+                entry: file.name().to_string(),
+                declared,
+                actual,
+            });
+        }
+        Ok(data)
+    }
+
+    /// 读取一次中央目录并保持归档打开，之后按名称取条目无需再次打开与扫描
+    ///
+    /// 适合从同一个归档中反复取出大量指定条目的工具；返回的 [`PreparedArchive`]
+    /// 使用本解压器的大小限制、进度回调与 CRC 校验设置。
+    pub fn prepare(&self) -> Result<PreparedArchive<'_>, DecodeError> {
+        let mut archive = open_archive(self.source().as_ref())?;
+        let mut index = HashMap::with_capacity(archive.len());
+        for i in 0..archive.len() {
+            index
+                .entry(archive.by_index_raw(i)?.name().to_string())
+                .or_insert(i);
+        }
+        Ok(PreparedArchive {
+            extractor: self,
+            archive,
+            index,
+        })
+    }
+
+    /// [`extract_entry_to_writer`](Self::extract_entry_to_writer) 的主体
+    fn write_entry<W: Write>(
+        &self,
+        file: &mut ZipFile<'_>,
+        writer: &mut W,
+    ) -> Result<u64, DecodeError> {
+        let ctx = ExtractContext::new(self.progress.as_ref().map(|callback| {
+            ProgressTracker::new(Arc::clone(callback), file.size(), self.progress_interval)
+        }));
+        let written = self.copy_entry(file, writer, &ctx, self.verify_crc)?;
+        writer.flush()?;
+        Ok(written)
+    }
+
+    /// 把所有文件条目解压到内存，按归档顺序返回 `(条目名, 数据)`
+    ///
+    /// 实际解压数据超过 [`spill_threshold`](Self::spill_threshold) 的条目转存到临时文件
+    /// （位于 [`temp_dir`](Self::temp_dir)，未设置时为系统临时目录），返回 [`EntryData::OnDisk`]，
+    /// 因此少数超大条目不会占满内存。按实际读出的字节判断，不信任条目声明的大小。
+    /// 名称转换、过滤与大小限制等与 [`extract`](Self::extract) 相同，目录条目不返回。
+    /// 出错时已创建的临时文件会被删除。
+    pub fn extract_to_memory(&self) -> Result<Vec<(String, EntryData)>, DecodeError> {
+        let mut archive = open_archive(self.source().as_ref())?;
+        let candidates: Vec<usize> = (0..archive.len()).collect();
+        let (entries, bytes_total) = self.plan(
+            &mut archive,
+            &candidates,
+            &HashSet::new(),
+            &mut ExtractionReport::default(),
+        )?;
+        let ctx = ExtractContext::new(self.progress.as_ref().map(|callback| {
+            ProgressTracker::new(Arc::clone(callback), bytes_total, self.progress_interval)
+        }));
+        let spill_dir = self.temp_dir.clone().unwrap_or_else(std::env::temp_dir);
+
+        let mut results = Vec::with_capacity(entries.len());
+        let mut spilled = Vec::new();
+        for entry in &entries {
+            let mut file = archive.by_index(entry.index)?;
+            if file.is_dir() {
+                continue;
+            }
+            let mut writer = SpillWriter::new(self.spill_threshold, &spill_dir);
+            self.copy_entry(&mut file, &mut writer, &ctx, self.verify_crc)?;
+            let data = match writer.finish()? {
+                Spilled::Memory(data) => EntryData::InMemory(data),
+                Spilled::Disk(temp) => {
+                    let path = temp.path.clone();
+                    spilled.push(temp);
+                    EntryData::OnDisk(path)
+                }
+            };
+            results.push((file.name().to_string(), data));
+        }
+        // 全部成功后才保留临时文件
+        for temp in spilled {
+            temp.keep();
+        }
+        Ok(results)
+    }
+
+    /// 解压到内存并把文本条目自动解码为 UTF-8，按条目名排序返回
+    ///
+    /// 扩展名（大小写不敏感，可带前导点）在 `text_extensions` 中的条目视为文本，
+    /// 按 [`ExtensionPriors`] 的默认提示检测编码并宽松解码；其余条目原样返回。
+    /// 与 [`extract_to_memory`](Self::extract_to_memory) 一样遵守过滤与安全限制，
+    /// 文本条目转存的临时文件在解码后删除。
+    pub fn extract_decoded(
+        &self,
+        text_extensions: &[&str],
+    ) -> Result<BTreeMap<String, DecodedEntry>, DecodeError> {
+        let text_extensions: HashSet<String> = text_extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_lowercase())
+            .collect();
+        let priors = ExtensionPriors::default();
+
+        let mut decoded = BTreeMap::new();
+        for (name, data) in self.extract_to_memory()? {
+            let is_text = Path::new(&name)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| text_extensions.contains(&ext.to_lowercase()));
+            let entry = if is_text {
+                let bytes = match data {
+                    EntryData::InMemory(bytes) => bytes,
+                    EntryData::OnDisk(path) => {
+                        let bytes = std::fs::read(&path)?;
+                        let _ = std::fs::remove_file(&path);
+                        bytes
+                    }
+                };
+                let hint = priors.for_path(Path::new(&name));
+                DecodedEntry::Text(decode::decode_with_hint(&bytes, hint).decoded)
+            } else {
+                DecodedEntry::Binary(data)
+            };
+            decoded.insert(name, entry);
+        }
+        Ok(decoded)
+    }
+
+    /// 检测自解压（SFX）归档：返回 ZIP 数据之前的可执行存根长度，普通 ZIP 返回 `None`
+    ///
+    /// 通过扫描中央目录结束记录定位归档起点。zip 库会自动跳过这类前导数据，
+    /// 因此 SFX 归档无需额外设置即可直接解压；找不到记录时返回 [`DecodeError::NotAnArchive`]。
+    pub fn sfx_stub_len(&self) -> Result<Option<u64>, DecodeError> {
+        let mut reader = self.source().open()?;
+        match EndOfCentralDirectory::find(&mut reader)? {
+            Some(eocd) => Ok(Some(eocd.prefix_len()).filter(|&len| len > 0)),
+            None => Err(DecodeError::NotAnArchive),
+        }
+    }
+
+    /// 扫描中央目录，确定需要解压的条目
+    ///
+    /// 返回待解压的条目及其声明的解压后总字节数，被跳过的条目记入 `report`。
+    /// 只考虑 `candidates` 中的索引；`completed` 为检查点中记录的 `(索引, CRC32)`。
+    fn plan(
+        &self,
+        archive: &mut ZipArchive<Box<dyn ReadSeek>>,
+        candidates: &[usize],
+        completed: &HashSet<(usize, u32)>,
+        report: &mut ExtractionReport,
+    ) -> Result<(Vec<PlannedEntry>, u64), DecodeError> {
+        if let Some(limit) = self.max_entries
+            && archive.len() > limit
+        {
+            return Err(DecodeError::TooManyEntries {
+                limit,
+                found: archive.len(),
+            });
+        }
+        let base_dir = self.base_dir()?;
+
+        let mut entries = Vec::with_capacity(candidates.len());
+        let mut bytes_total = 0;
+        // 折叠后的路径 -> 首个占用它的条目名
+        let mut seen: HashMap<String, String> = HashMap::new();
+
+        for &i in candidates {
+            let mut file = archive.by_index_raw(i)?;
+            let name = match &self.name_transform {
+                Some(transform) => match transform(file.name()) {
+                    Some(name) => name,
+                    None => {
+                        self.skip(report, file.name(), SkipReason::NameRejected);
+                        continue;
+                    }
+                },
+                None => file.name().to_string(),
+            };
+
+            if self.skip_mac_metadata && is_mac_metadata(&name) {
+                self.skip(report, file.name(), SkipReason::MacMetadata);
+                continue;
+            }
+
+            if !self.symlinks_as_files && is_symlink(&file) {
+                log::warn!("条目 {:?} 是符号链接，已跳过", file.name());
+                self.skip(report, file.name(), SkipReason::Symlink);
+                continue;
+            }
+
+            let name = if self.lowercase_names {
+                name.to_lowercase()
+            } else {
+                name
+            };
+
+            let name = match absolute_prefix_len(&name) {
+                0 => name,
+                prefix => match self.absolute_paths {
+                    AbsolutePathPolicy::Relativize => {
+                        log::warn!("条目 {:?} 是绝对路径，已解压到输出目录内", file.name());
+                        report.relativized.push(file.name().to_string());
+                        name[prefix..].to_string()
+                    }
+                    AbsolutePathPolicy::Skip => {
+                        self.skip(report, file.name(), SkipReason::AbsolutePath);
+                        continue;
+                    }
+                    AbsolutePathPolicy::Reject => {
+                        return Err(unsafe_path(file.name(), &base_dir, &name));
+                    }
+                },
+            };
+
+            if self.strict_paths && is_traversal(&name) {
+                return Err(unsafe_path(file.name(), &base_dir, &name));
+            }
+
+            let sanitized = sanitize_name(&name);
+            if let Some(allowed) = &self.allowed_extensions
+                && !file.is_dir()
+                && !sanitized
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| allowed.contains(&ext.to_lowercase()))
+            {
+                self.skip(report, file.name(), SkipReason::DisallowedExtension);
+                continue;
+            }
+
+            if !file.is_dir()
+                && (self.min_entry_size.is_some_and(|min| file.size() < min)
+                    || self.max_entry_size.is_some_and(|max| file.size() > max))
+            {
+                self.skip(report, file.name(), SkipReason::SizeOutOfRange);
+                continue;
+            }
+
+            if let Some(types) = &self.content_types
+                && !file.is_dir()
+            {
+                let entry_name = file.name().to_string();
+                drop(file);
+                let matched = sniff_mime(archive, i)?.is_some_and(|mime| {
+                    types.iter().any(|t| {
+                        if t.ends_with('/') {
+                            mime.starts_with(t.as_str())
+                        } else {
+                            mime == t
+                        }
+                    })
+                });
+                if !matched {
+                    self.skip(report, &entry_name, SkipReason::ContentType);
+                    continue;
+                }
+                file = archive.by_index_raw(i)?;
+            }
+
+            let Some(mut out_path) = self.output_path(&base_dir, &sanitized) else {
+                self.skip(report, file.name(), SkipReason::Unrouted);
+                continue;
+            };
+            if self.gzip_output && !file.is_dir() {
+                out_path.as_mut_os_string().push(".gz");
+            }
+
+            if (self.collisions != CollisionPolicy::Allow || self.lowercase_names) && !file.is_dir()
+            {
+                // Allow 下只拦截小写化造成的完全重名
+                let key = if self.collisions == CollisionPolicy::Allow {
+                    sanitized.to_string_lossy().into_owned()
+                } else {
+                    fold_name(&sanitized)
+                };
+                if let Some(first) = seen.get(&key) {
+                    if self.collisions == CollisionPolicy::Error {
+                        return Err(DecodeError::CaseCollision {
+                            first: first.clone(),
+                            second: file.name().to_string(),
+                        });
+                    }
+                    log::warn!("条目 {:?} 与 {:?} 冲突，已跳过", file.name(), first);
+                    self.skip(
+                        report,
+                        file.name(),
+                        SkipReason::CaseCollision(first.clone()),
+                    );
+                    continue;
+                }
+                seen.insert(key, file.name().to_string());
+            }
+
+            if completed.contains(&(i, file.crc32()))
+                && (file.is_dir()
+                    || !self.verify_crc
+                    || file_crc(&out_path).is_ok_and(|crc| crc == file.crc32()))
+            {
+                if !file.is_dir() {
+                    report
+                        .entry_crcs
+                        .push((file.name().to_string(), file.crc32(), file.size()));
+                }
+                self.skip(report, file.name(), SkipReason::Resumed);
+                continue;
+            }
+
+            if !self.dirs_only {
+                bytes_total += file.size();
+            }
+            entries.push(PlannedEntry { index: i, out_path });
+        }
+        Ok((entries, bytes_total))
+    }
+
+    /// 记录一个被跳过的条目
+    fn skip(&self, report: &mut ExtractionReport, name: &str, reason: SkipReason) {
+        if let Some(events) = &self.events {
+            let _ = events.send(ExtractEvent::EntrySkipped(name.to_string(), reason.clone()));
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(name, reason = ?reason, "skipped");
+        report.skipped.push((name.to_string(), reason));
+    }
+
+    /// 压缩比超过警告阈值时记录可疑条目
+    fn check_ratio(&self, report: &mut ExtractionReport, file: &ZipFile<'_>, bytes: u64) {
+        let Some(threshold) = self.warn_compression_ratio else {
+            return;
+        };
+        let ratio = bytes as f64 / file.compressed_size().max(1) as f64;
+        if ratio > threshold {
+            log::warn!(
+                "条目 {:?} 的压缩比 {:.1} 超过警告阈值 {}",
+                file.name(),
+                ratio,
+                threshold
+            );
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                name = file.name(),
+                ratio,
+                threshold,
+                "suspicious compression ratio"
+            );
+            report
+                .suspicious_entries
+                .push((file.name().to_string(), ratio));
+        }
+    }
+
+    /// 当前生效的数据源
+    fn source(&self) -> Arc<dyn ArchiveSource> {
+        if let Some(source) = &self.source {
+            return Arc::clone(source);
+        }
+        if self.mmap {
+            match MmapSource::open_path(&self.zip_path) {
+                Ok(source) => return Arc::new(source),
+                Err(e) => log::warn!(
+                    "无法内存映射 {}, 回退为文件读取: {}",
+                    self.zip_path.display(),
+                    e
+                ),
+            }
+        }
+        Arc::new(FileSource::new(&self.zip_path).buffer_size(self.read_buffer_size))
+    }
+
+    /// 顺序解压（单线程）
+    fn extract_sequential(
+        &self,
+        archive: &mut ZipArchive<Box<dyn ReadSeek>>,
+        entries: &[PlannedEntry],
+        ctx: &ExtractContext,
+        report: &mut ExtractionReport,
+    ) -> Result<(), DecodeError> {
+        for entry in entries {
+            let mut file = archive.by_index(entry.index)?;
+            self.extract_entry(entry, &mut file, ctx, report)?;
+        }
+        Ok(())
+    }
+
+    /// 并行解压（多线程）
+    fn extract_parallel(
+        &self,
+        source: &dyn ArchiveSource,
+        entries: &[PlannedEntry],
+        num_threads: usize,
+        ctx: &ExtractContext,
+        report: &mut ExtractionReport,
+    ) -> Result<(), DecodeError> {
+        // 每个分块一个工作线程；条目数不是线程数的整数倍时分块可能少于 `num_threads`
+        let chunk_size = entries.len() / num_threads + 1;
+        let workers = entries.len().div_ceil(chunk_size);
+        report.threads_used = workers;
+
+        // 创建线程池
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(workers)
+            .build()
+            .map_err(io::Error::other)?;
+
+        // 各工作线程结束时把自己的统计合并进来
+        let total = Mutex::new(ExtractionReport::default());
+        // 第一个失败条目的错误；设置后其余工作线程在当前条目完成后停止
+        let first_error = Mutex::new(None);
+        let failed = AtomicBool::new(false);
+        // 处理条目时发生的 panic：(条目名, panic 信息)
+        // 共享状态在 panic 后仍保持一致，因此这里及 `ExtractContext` 中的锁被毒化时直接取回数据，
+        // 避免一个条目的 panic 让其他工作线程跟着 panic
+        let panics = Mutex::new(Vec::new());
+
+        pool.scope(|s| {
+            for chunk in entries.chunks(chunk_size) {
+                let extractor = self; // 借用 self
+                let total = &total;
+                let first_error = &first_error;
+                let failed = &failed;
+                let panics = &panics;
+                #[cfg(feature = "tracing")]
+                let parent = tracing::Span::current();
+
+                s.spawn(move |_| {
+                    // 工作线程不会继承调用线程的 span，手动挂到 `extract` 之下
+                    #[cfg(feature = "tracing")]
+                    let _span = tracing::info_span!(parent: &parent, "worker").entered();
+                    // 每个工作线程持有独立的归档句柄，解压时无需加锁
+                    // 与 `open_archive` 一样，打开句柄的 IO 错误报告为 `DecodeError::Io`
+                    let mut archive = match source
+                        .open()
+                        .map_err(DecodeError::from)
+                        .and_then(|reader| Ok(ZipArchive::new(reader)?))
+                    {
+                        Ok(archive) => archive,
+                        Err(e) => {
+                            // 打不开归档的工作线程无法处理它的条目，与条目出错一样中止解压
+                            first_error
+                                .lock()
+                                .unwrap_or_else(PoisonError::into_inner)
+                                .get_or_insert(e);
+                            failed.store(true, Ordering::Relaxed);
+                            return;
+                        }
+                    };
+
+                    let mut report = ExtractionReport::default();
+                    for entry in chunk {
+                        if failed.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        let mut name = None;
+                        // 单个条目的 panic 只终止该条目，归于它并在全部线程结束后报告
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                            let mut file = archive.by_index(entry.index)?;
+                            name = Some(file.name().to_string());
+                            extractor.extract_entry(entry, &mut file, ctx, &mut report)
+                        }));
+                        let name = name.unwrap_or_else(|| format!("#{}", entry.index));
+                        match result {
+                            Ok(Ok(())) => {}
+                            Ok(Err(e)) => {
+                                first_error
+                                    .lock()
+                                    .unwrap_or_else(PoisonError::into_inner)
+                                    .get_or_insert(e);
+                                failed.store(true, Ordering::Relaxed);
+                                break;
+                            }
+                            Err(payload) => panics
+                                .lock()
+                                .unwrap_or_else(PoisonError::into_inner)
+                                .push((name, panic_message(&*payload))),
+                        }
+                    }
+                    total
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .merge(report);
+                });
+            }
+        });
+
+        report.merge(total.into_inner().unwrap_or_else(PoisonError::into_inner));
+        if let Some(e) = first_error
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+        {
+            return Err(e);
+        }
+        match panics
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .into_iter()
+            .next()
+        {
+            Some((entry, message)) => Err(DecodeError::WorkerPanicked { entry, message }),
+            None => Ok(()),
+        }
+    }
+
+    /// 解压单个条目（目录或文件），结果计入 `report`
+    fn extract_entry(
+        &self,
+        entry: &PlannedEntry,
+        file: &mut ZipFile<'_>,
+        ctx: &ExtractContext,
+        report: &mut ExtractionReport,
+    ) -> Result<(), DecodeError> {
+        let index = entry.index;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("entry", index, name = file.name()).entered();
+        let mut out_path = self.platform_path(file.name(), entry.out_path.clone())?;
+        let info =
+            (self.events.is_some() || self.entry_start.is_some() || self.entry_done.is_some())
+                .then(|| EntryInfo::from_zip(index, file));
+        if let (Some(hook), Some(info)) = (&self.entry_start, &info) {
+            hook(info);
+        }
+
+        if file.is_dir() {
+            ctx.ensure_dir(&out_path)?;
+            report.dirs_created += 1;
+        } else if self.on_conflict.is_none()
+            && self.overwrite == OverwritePolicy::IfNewer
+            && !is_newer(file, &out_path)?
+        {
+            self.skip(report, file.name(), SkipReason::NotNewer);
+            return Ok(());
+        } else if self.on_conflict.is_none()
+            && self.overwrite == OverwritePolicy::IfDifferent
+            && is_unchanged(file, &out_path)?
+        {
+            self.skip(report, file.name(), SkipReason::Unchanged);
+            return Ok(());
+        } else if self.dirs_only {
+            if let Some(parent) = out_path.parent() {
+                ctx.ensure_dir(parent)?;
+            }
+            return Ok(());
+        } else {
+            let mut replace = self.overwrite.replaces_existing();
+            if let Some(resolve) = &self.on_conflict {
+                replace = false;
+                if std::fs::symlink_metadata(&out_path).is_ok() {
+                    match resolve(&out_path, &EntryInfo::from_zip(index, file)) {
+                        ConflictResolution::Overwrite => replace = true,
+                        ConflictResolution::Skip => {
+                            self.skip(report, file.name(), SkipReason::AlreadyExists);
+                            return Ok(());
+                        }
+                        ConflictResolution::Rename(target) => {
+                            out_path = renamed_path(&out_path, target)?;
+                        }
+                        ConflictResolution::Abort => {
+                            return Err(DecodeError::AlreadyExists(out_path));
+                        }
+                    }
+                }
+            }
+            let (slot, buffers) = match &ctx.adaptive {
+                Some(adaptive) => adaptive
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .next(),
+                None => (None, self.buffer_sizes(index, file)),
+            };
+            let started = (self.slowest_entries > 0 || slot.is_some()).then(Instant::now);
+            match self.extract_file(file, &out_path, ctx, buffers, replace) {
+                Ok(bytes) => {
+                    if let Some(started) = started {
+                        let elapsed = started.elapsed();
+                        if let (Some(slot), Some(adaptive)) = (slot, &ctx.adaptive) {
+                            adaptive
+                                .lock()
+                                .unwrap_or_else(PoisonError::into_inner)
+                                .record(slot, bytes, elapsed);
+                        }
+                        if self.slowest_entries > 0 {
+                            report
+                                .slowest_entries
+                                .push((file.name().to_string(), elapsed));
+                        }
+                    }
+                    self.check_ratio(report, file, bytes);
+                    report.bytes_written += bytes;
+                    report.compressed_bytes += file.compressed_size();
+                    report.files_extracted += 1;
+                    if bytes == 0 {
+                        report.empty_files += 1;
+                    }
+                    report
+                        .entry_crcs
+                        .push((file.name().to_string(), file.crc32(), bytes));
+                    if self.metadata_sidecars {
+                        write_sidecar(file, &out_path)?;
+                    }
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(bytes, compressed = file.compressed_size(), "extracted");
+                }
+                Err(DecodeError::Io(e)) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    return match self.overwrite {
+                        OverwritePolicy::Skip if self.on_conflict.is_none() => {
+                            self.skip(report, file.name(), SkipReason::AlreadyExists);
+                            Ok(())
+                        }
+                        _ => Err(DecodeError::AlreadyExists(out_path)),
+                    };
+                }
+                Err(DecodeError::CrcMismatch {
+                    entry,
+                    expected,
+                    actual,
+                }) if self.collect_crc_errors => {
+                    log::warn!("条目 {:?} 的 CRC 校验失败，继续解压其余条目", entry);
+                    report.corrupt.push(Mismatch {
+                        entry,
+                        path: out_path,
+                        kind: MismatchKind::CrcDiffers { expected, actual },
+                    });
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        if let Some(checkpoint) = &ctx.checkpoint {
+            let mut checkpoint = checkpoint.lock().unwrap_or_else(PoisonError::into_inner);
+            writeln!(checkpoint, "{} {:08x}", index, file.crc32())?;
+        }
+        if let (Some(hook), Some(info)) = (&self.entry_done, &info) {
+            hook(info, &out_path);
+        }
+        if let (Some(events), Some(info)) = (&self.events, info) {
+            let _ = events.send(ExtractEvent::EntryExtracted(info));
+        }
+        Ok(())
+    }
+
+    /// 未路由条目所在的目录：`output_dir`，设置了子目录时为 `output_dir/subdir`
+    fn base_dir(&self) -> Result<PathBuf, DecodeError> {
+        let Some(subdir) = &self.subdir else {
+            return Ok(self.output_dir.clone());
+        };
+        let relative = sanitize_name(subdir);
+        if absolute_prefix_len(subdir) > 0
+            || is_traversal(subdir)
+            || relative.as_os_str().is_empty()
+        {
+            return Err(unsafe_path(subdir, &self.output_dir, subdir));
+        }
+        Ok(self.output_dir.join(relative))
+    }
+
+    /// 按前缀路由计算条目的输出路径，应跳过时返回 `None`
+    fn output_path(&self, base_dir: &Path, name: &Path) -> Option<PathBuf> {
+        if self.routes.is_empty() {
+            return Some(base_dir.join(name));
+        }
+        let mut components = name.components();
+        let routed = components
+            .next()
+            .and_then(|top| top.as_os_str().to_str())
+            .and_then(|top| self.routes.get(top));
+        match routed {
+            Some(dir) => Some(dir.join(components.as_path())),
+            None if self.skip_unrouted => None,
+            None => Some(base_dir.join(name)),
+        }
+    }
+
+    /// 按平台限制调整输出路径（目前只处理 Windows 的 MAX_PATH）
+    #[cfg(windows)]
+    fn platform_path(&self, entry: &str, path: PathBuf) -> Result<PathBuf, DecodeError> {
+        use std::os::windows::ffi::OsStrExt;
+
+        let wide_len = path.as_os_str().encode_wide().count();
+        if wide_len < MAX_PATH || path.as_os_str().to_string_lossy().starts_with(r"\\?\") {
+            return Ok(path);
+        }
+        if !self.long_paths {
+            return Err(DecodeError::PathTooLong {
+                entry: entry.to_string(),
+                path,
+            });
+        }
+        let absolute = std::path::absolute(&path)?;
+        Ok(PathBuf::from(long_path_prefix(&absolute.to_string_lossy())))
+    }
+
+    #[cfg(not(windows))]
+    fn platform_path(&self, _entry: &str, path: PathBuf) -> Result<PathBuf, DecodeError> {
+        Ok(path)
+    }
+
+    /// 创建临时目录，不在输出目录所在的文件系统时发出警告
+    fn prepare_temp_dir(&self) -> Result<PathBuf, DecodeError> {
+        let dir = self.temp_dir.as_ref().unwrap_or(&self.output_dir);
+        std::fs::create_dir_all(dir)?;
+        std::fs::create_dir_all(&self.output_dir)?;
+        if !same_filesystem(dir, &self.output_dir)? {
+            log::warn!(
+                "临时目录 {:?} 与输出目录 {:?} 不在同一文件系统，重命名不是原子的",
+                dir,
+                self.output_dir
+            );
+        }
+        Ok(dir.clone())
+    }
+
+    /// 条目使用的 (读块, 写缓冲) 大小
+    fn buffer_sizes(&self, index: usize, file: &ZipFile<'_>) -> (usize, usize) {
+        match &self.buffer_sizer {
+            Some(sizer) => {
+                let (read, write) = sizer(&EntryInfo::from_zip(index, file));
+                (read.max(1), write.max(1))
+            }
+            None => (COPY_BUFFER_SIZE, self.write_buffer_size),
+        }
+    }
+
+    /// 提取单个文件（核心提取逻辑），返回写出的字节数
+    ///
+    /// `replace` 为假时目标已存在返回 `AlreadyExists` 的 IO 错误。
+    fn extract_file(
+        &self,
+        file: &mut ZipFile<'_>,
+        output_path: &Path,
+        ctx: &ExtractContext,
+        (read_size, write_size): (usize, usize),
+        replace: bool,
+    ) -> Result<u64, DecodeError> {
+        // 确保父目录存在（同一目录在一次解压中只创建一次）
+        if let Some(parent) = output_path.parent() {
+            ctx.ensure_dir(parent)?;
+        }
+
+        if let Some(temp_dir) = &ctx.temp_dir {
+            let temp = TempFile::create(temp_dir)?;
+            let written = self.write_output(file, temp.file()?, ctx, (read_size, write_size))?;
+            temp.persist(output_path, replace)?;
+            return Ok(written);
+        }
+
+        // 使用缓冲写入器；不允许覆盖时用 create_new 原子地判断文件是否已存在
+        let mut options = OpenOptions::new();
+        options.write(true);
+        match (replace, self.output_mode) {
+            (false, _) => options.create_new(true),
+            (true, OutputMode::Truncate) => options.create(true).truncate(true),
+            (true, OutputMode::Append) => options.create(true).append(true),
+            (true, OutputMode::At(_)) => options.create(true),
+        };
+        let mut file_out = options.open(output_path)?;
+        if let (true, OutputMode::At(offset)) = (replace, self.output_mode) {
+            file_out.seek(SeekFrom::Start(offset))?;
+        }
+
+        self.write_output(file, file_out, ctx, (read_size, write_size))
+    }
+
+    /// 通过写缓冲（开启 gzip 输出时再经过 gzip 编码器）把条目数据写入已打开的输出文件
+    fn write_output(
+        &self,
+        file: &mut ZipFile<'_>,
+        file_out: File,
+        ctx: &ExtractContext,
+        (read_size, write_size): (usize, usize),
+    ) -> Result<u64, DecodeError> {
+        let mut writer = BufWriter::with_capacity(write_size, file_out);
+        #[cfg(feature = "gzip")]
+        let written = if self.gzip_output {
+            let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+            let written = self.copy_chunked(file, &mut encoder, ctx, self.verify_crc, read_size)?;
+            writer = encoder.finish()?;
+            written
+        } else {
+            self.copy_chunked(file, &mut writer, ctx, self.verify_crc, read_size)?
+        };
+        #[cfg(not(feature = "gzip"))]
+        let written = self.copy_chunked(file, &mut writer, ctx, self.verify_crc, read_size)?;
+        let file_out = writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?;
+        if self.drop_page_cache {
+            drop_page_cache(&file_out)?;
+        }
+        Ok(written)
+    }
+
+    /// 把条目数据拷贝到 `writer`，同时执行大小限制、CRC 校验与进度上报
+    ///
+    /// 所有统计都基于实际读出的字节，不依赖条目声明的大小。
+    fn copy_entry<W: Write>(
+        &self,
+        file: &mut ZipFile<'_>,
+        writer: &mut W,
+        ctx: &ExtractContext,
+        verify_crc: bool,
+    ) -> Result<u64, DecodeError> {
+        self.copy_chunked(file, writer, ctx, verify_crc, COPY_BUFFER_SIZE)
+    }
+
+    /// 与 [`copy_entry`](Self::copy_entry) 相同，每次从解压流读取 `chunk` 字节
+    fn copy_chunked<W: Write>(
+        &self,
+        file: &mut ZipFile<'_>,
+        writer: &mut W,
+        ctx: &ExtractContext,
+        verify_crc: bool,
+        chunk: usize,
+    ) -> Result<u64, DecodeError> {
+        let expected_crc = file.crc32();
+        let ratio_limit = self
+            .max_compression_ratio
+            .map(|ratio| (file.compressed_size().max(1) as f64 * ratio) as u64);
+
+        // 复用当前线程的拷贝缓冲区，避免每个条目重新分配
+        let mut buffer = PooledBuffer::with_len(chunk);
+        let buffer = buffer.as_mut_slice();
+        let mut hasher = crc32fast::Hasher::new();
+        let mut manifest = self.manifest.as_ref().and_then(|(algorithm, expected)| {
+            expected
+                .get(file.name())
+                .map(|digest| (algorithm, digest, algorithm.hasher()))
+        });
+        let mut pending = 0u64; // 尚未上报的字节数
+        let mut written = 0u64;
+        loop {
+            let n = match file.read(buffer) {
+                Ok(0) => break,
+                Ok(n) => n,
+                // zip 内部的 CRC 检查在读到末尾时报错，交由下面统一处理
+                Err(e) if is_checksum_error(&e) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            written += n as u64;
+
+            if let Some(limit) = ratio_limit
+                && written > limit
+            {
+                return Err(DecodeError::CompressionRatioExceeded {
+                    entry: file.name().to_string(),
+                    limit: self.max_compression_ratio.unwrap_or_default(),
+                });
+            }
+            let total = ctx.bytes_written.fetch_add(n as u64, Ordering::Relaxed) + n as u64;
+            if let Some(limit) = self.max_total_size
+                && total > limit
+            {
+                return Err(DecodeError::TotalSizeExceeded { limit });
+            }
+
+            if verify_crc {
+                hasher.update(&buffer[..n]);
+            }
+            if let Some((_, _, digest)) = &mut manifest {
+                digest.update(&buffer[..n]);
+            }
+            writer.write_all(&buffer[..n])?;
+
+            if let Some(tracker) = &ctx.progress {
+                pending += n as u64;
+                if pending >= tracker.interval {
+                    tracker.advance(pending);
+                    pending = 0;
+                }
+            }
+        }
+
+        if let Some(tracker) = &ctx.progress
+            && pending > 0
+        {
+            tracker.advance(pending);
+        }
+
+        if verify_crc {
+            let actual = hasher.finalize();
+            if actual != expected_crc {
+                return Err(DecodeError::CrcMismatch {
+                    entry: file.name().to_string(),
+                    expected: expected_crc,
+                    actual,
+                });
+            }
+        }
+        if let Some((algorithm, expected, digest)) = manifest {
+            let actual = digest.finalize_hex();
+            if actual != *expected {
+                return Err(DecodeError::DigestMismatch {
+                    entry: file.name().to_string(),
+                    algorithm: algorithm.name(),
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// 由 [`ZipExtractor::prepare`] 打开的归档，缓存了条目名到索引的映射
+pub struct PreparedArchive<'a> {
+    extractor: &'a ZipExtractor,
+    archive: ZipArchive<Box<dyn ReadSeek>>,
+    /// 条目名 -> 中央目录索引（同名条目取第一个）
+    index: HashMap<String, usize>,
+}
+
+impl PreparedArchive<'_> {
+    /// 条目数
+    pub fn len(&self) -> usize {
+        self.archive.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.archive.len() == 0
+    }
+
+    /// 条目在中央目录中的索引
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    /// 把名为 `name` 的条目解压到 `writer`，行为与
+    /// [`ZipExtractor::extract_entry_to_writer`] 相同
+    pub fn extract_entry_to_writer<W: Write>(
+        &mut self,
+        name: &str,
+        writer: &mut W,
+    ) -> Result<u64, DecodeError> {
+        let index = self.index_of(name).ok_or(ZipError::FileNotFound)?;
+        let mut file = self.archive.by_index(index)?;
+        self.extractor.write_entry(&mut file, writer)
+    }
+
+    /// 打开名为 `name` 的条目的解压读取器，条目不存在时返回 `ZipError::FileNotFound`
+    ///
+    /// 直接读取 zip 库的解压流：不受大小限制约束，只有 zip 库自身的 CRC 检查。
+    pub fn open_entry_reader(&mut self, name: &str) -> Result<ZipFile<'_>, DecodeError> {
+        let index = self.index_of(name).ok_or(ZipError::FileNotFound)?;
+        Ok(self.archive.by_index(index)?)
+    }
+}
+
+/// 自适应缓冲的一组候选大小及其累计的测量结果
+struct BufferTrial {
+    /// (读块, 写缓冲) 大小
+    sizes: (usize, usize),
+    bytes: u64,
+    elapsed: Duration,
+    samples: usize,
+}
+
+impl BufferTrial {
+    /// 实测吞吐量（字节/秒）
+    fn throughput(&self) -> f64 {
+        self.bytes as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+}
+
+/// 自适应缓冲：前若干个文件轮流试用几组缓冲区大小，按实测吞吐量选出最快的一组
+struct AdaptiveBuffers {
+    candidates: Vec<BufferTrial>,
+    /// 已分配出去的试用次数，用于轮换候选
+    trials: usize,
+    chosen: Option<(usize, usize)>,
+}
+
+/// 每个候选至少需要的样本数
+const ADAPTIVE_SAMPLES: usize = 3;
+
+impl AdaptiveBuffers {
+    /// 以配置的大小为基准，另试其 4 倍与 1/4（不小于 4KB）
+    fn new((read, write): (usize, usize)) -> Self {
+        let candidates = [4, 2, 0]
+            .into_iter()
+            .map(|shift| {
+                let scale = |size: usize| ((size << shift) >> 2).max(4096);
+                BufferTrial {
+                    sizes: (scale(read), scale(write)),
+                    bytes: 0,
+                    elapsed: Duration::ZERO,
+                    samples: 0,
+                }
+            })
+            .collect();
+        AdaptiveBuffers {
+            candidates,
+            trials: 0,
+            chosen: None,
+        }
+    }
+
+    /// 下一个条目使用的候选序号与大小
+    fn next(&mut self) -> (Option<usize>, (usize, usize)) {
+        if let Some(sizes) = self.chosen {
+            return (None, sizes);
+        }
+        let slot = self.trials % self.candidates.len();
+        self.trials += 1;
+        (Some(slot), self.candidates[slot].sizes)
+    }
+
+    /// 记录一次试用的结果，所有候选的样本都足够时选出吞吐量最高的一组
+    fn record(&mut self, slot: usize, bytes: u64, elapsed: Duration) {
+        let trial = &mut self.candidates[slot];
+        trial.bytes += bytes;
+        trial.elapsed += elapsed;
+        trial.samples += 1;
+        if self.chosen.is_none()
+            && self
+                .candidates
+                .iter()
+                .all(|trial| trial.samples >= ADAPTIVE_SAMPLES)
+        {
+            self.chosen = self
+                .candidates
+                .iter()
+                .max_by(|a, b| a.throughput().total_cmp(&b.throughput()))
+                .map(|trial| trial.sizes);
+        }
+    }
+}
+
+/// 拷贝缓冲区大小
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// [`ZipExtractor::read_entry_to_vec`] 按声明大小预分配的上限
+const MAX_PREALLOC: usize = 64 * 1024 * 1024;
+
+thread_local! {
+    /// 每个线程复用的拷贝缓冲区（rayon 工作线程在整个解压过程中各持有一个）
+    static COPY_BUFFER: Cell<Vec<u8>> = const { Cell::new(Vec::new()) };
+}
+
+/// 从线程本地池借出的拷贝缓冲区，离开作用域时归还
+///
+/// 借出期间池为空，因此回调中嵌套解压也是安全的（只是会临时分配新缓冲区）。
+struct PooledBuffer(Vec<u8>);
+
+impl PooledBuffer {
+    /// 借出长度为 `len` 的缓冲区（池中的缓冲区容量会保留，供之后的条目复用）
+    fn with_len(len: usize) -> Self {
+        let mut buffer = COPY_BUFFER.take();
+        buffer.resize(len, 0);
+        Self(buffer)
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        COPY_BUFFER.set(std::mem::take(&mut self.0));
+    }
+}
+
+/// [`ZipExtractor::try_entries`] 返回的逐条目解压迭代器
+pub struct TryEntries<'a> {
+    extractor: &'a ZipExtractor,
+    state: Option<RunState>,
+    /// 打开或扫描阶段的错误，作为唯一的结果产出
+    pending: Option<DecodeError>,
+    /// 下一个要处理的条目在 `state.entries` 中的位置
+    next: usize,
+    report: ExtractionReport,
+    start_time: Instant,
+}
+
+impl TryEntries<'_> {
+    /// 结束迭代，返回到目前为止的统计结果（未处理的条目不计入）
+    pub fn into_report(mut self) -> ExtractionReport {
+        if let Some(state) = &self.state {
+            self.report.buffer_sizes = state.ctx.chosen_buffer_sizes();
+        }
+        self.report.keep_slowest(self.extractor.slowest_entries);
+        self.report.duration = self.start_time.elapsed();
+        self.report
+    }
+}
+
+impl Iterator for TryEntries<'_> {
+    type Item = Result<EntryInfo, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.take() {
+            return Some(Err(e));
+        }
+        let state = self.state.as_mut()?;
+        let entry = state.entries.get(self.next)?;
+        self.next += 1;
+        let mut file = match state.archive.by_index(entry.index) {
+            Ok(file) => file,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let info = EntryInfo::from_zip(entry.index, &file);
+        Some(
+            self.extractor
+                .extract_entry(entry, &mut file, &state.ctx, &mut self.report)
+                .map(|()| info),
+        )
+    }
+}
+
+/// 打开并扫描完成、即将开始写出的一次解压
+struct RunState {
+    source: Arc<dyn ArchiveSource>,
+    archive: ZipArchive<Box<dyn ReadSeek>>,
+    entries: Vec<PlannedEntry>,
+    ctx: ExtractContext,
+    /// 待解压条目声明的解压后总字节数
+    bytes_total: u64,
+}
+
+/// `plan` 选出的待解压条目
+struct PlannedEntry {
+    /// 中央目录中的索引
+    index: usize,
+    /// 经过名称转换、路径清理与前缀路由后的输出路径
+    out_path: PathBuf,
+}
+
+/// 一次解压过程中在工作线程间共享的状态
+struct ExtractContext {
+    progress: Option<ProgressTracker>,
+    /// 所有线程累计写出的字节数，用于总大小限制
+    bytes_written: AtomicU64,
+    /// 以追加方式打开的检查点文件
+    checkpoint: Option<Mutex<File>>,
+    /// 本次解压中已确认存在的目录
+    created_dirs: Mutex<HashSet<PathBuf>>,
+    /// 开启原子写入时临时文件所在的目录
+    temp_dir: Option<PathBuf>,
+    /// 开启自适应缓冲时的测量状态
+    adaptive: Option<Mutex<AdaptiveBuffers>>,
+}
+
+impl ExtractContext {
+    fn new(progress: Option<ProgressTracker>) -> Self {
+        Self {
+            progress,
+            bytes_written: AtomicU64::new(0),
+            checkpoint: None,
+            created_dirs: Mutex::new(HashSet::new()),
+            temp_dir: None,
+            adaptive: None,
+        }
+    }
+
+    /// 自适应缓冲最终选出的大小，尚未选出或未开启时为 `None`
+    fn chosen_buffer_sizes(&self) -> Option<(usize, usize)> {
+        let adaptive = self.adaptive.as_ref()?;
+        adaptive
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .chosen
+    }
+
+    /// 确保目录存在；已创建过的目录直接返回，不再访问文件系统
+    ///
+    /// 创建时不持有锁，两个线程偶尔会重复调用 `create_dir_all`，这是无害的。
+    fn ensure_dir(&self, dir: &Path) -> io::Result<()> {
+        if self
+            .created_dirs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(dir)
+        {
+            return Ok(());
+        }
+        std::fs::create_dir_all(dir)?;
+        self.created_dirs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(dir.to_path_buf());
+        Ok(())
+    }
+}
+
+/// 解压过程中的临时文件，未被 [`persist`](TempFile::persist) 时在析构时删除
+struct TempFile {
+    path: PathBuf,
+    persisted: bool,
+}
+
+impl TempFile {
+    fn create(dir: &Path) -> io::Result<Self> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let path = dir.join(format!(
+            ".auto-decode-{}-{}.part",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(Self {
+            path,
+            persisted: false,
+        })
+    }
+
+    fn file(&self) -> io::Result<File> {
+        OpenOptions::new().write(true).open(&self.path)
+    }
+
+    /// 保留在原位置，析构时不再删除
+    fn keep(mut self) {
+        self.persisted = true;
+    }
+
+    /// 移动到 `target`；`overwrite` 为假时目标已存在则返回 `AlreadyExists`
+    fn persist(self, target: &Path, overwrite: bool) -> io::Result<()> {
+        if overwrite {
+            self.persist_with(target, true, |from, to| std::fs::rename(from, to))
+        } else {
+            // 硬链接在目标已存在时失败，相当于原子的 create_new
+            self.persist_with(target, false, |from, to| std::fs::hard_link(from, to))
+        }
+    }
+
+    /// [`persist`](Self::persist) 的主体，`link` 为重命名或硬链接
+    ///
+    /// 重命名跨设备时、硬链接因任何原因（跨设备、vfat/exFAT 等不支持硬链接的文件系统）
+    /// 失败而目标并不存在时，退回为复制；复制失败时删除已创建的目标文件。
+    fn persist_with(
+        mut self,
+        target: &Path,
+        overwrite: bool,
+        link: impl FnOnce(&Path, &Path) -> io::Result<()>,
+    ) -> io::Result<()> {
+        match link(&self.path, target) {
+            // 重命名后临时文件已不存在；硬链接或复制时仍由析构删除
+            Ok(()) => self.persisted = overwrite,
+            Err(e) if overwrite && e.kind() != io::ErrorKind::CrossesDevices => return Err(e),
+            Err(e) if !overwrite && e.kind() == io::ErrorKind::AlreadyExists => return Err(e),
+            Err(_) => {
+                let mut options = OpenOptions::new();
+                options.write(true);
+                if overwrite {
+                    options.create(true).truncate(true);
+                } else {
+                    options.create_new(true);
+                }
+                let mut out = options.open(target)?;
+                if let Err(e) =
+                    File::open(&self.path).and_then(|mut src| io::copy(&mut src, &mut out))
+                {
+                    drop(out);
+                    let _ = std::fs::remove_file(target);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// 先写入内存，超过阈值后把已有内容连同后续数据转存到临时文件
+struct SpillWriter<'a> {
+    threshold: u64,
+    dir: &'a Path,
+    buffer: Vec<u8>,
+    disk: Option<(TempFile, BufWriter<File>)>,
+}
+
+/// [`SpillWriter::finish`] 的结果
+enum Spilled {
+    Memory(Vec<u8>),
+    Disk(TempFile),
+}
+
+impl<'a> SpillWriter<'a> {
+    fn new(threshold: u64, dir: &'a Path) -> Self {
+        Self {
+            threshold,
+            dir,
+            buffer: Vec::new(),
+            disk: None,
+        }
+    }
+
+    fn finish(self) -> io::Result<Spilled> {
+        match self.disk {
+            Some((temp, mut writer)) => {
+                writer.flush()?;
+                Ok(Spilled::Disk(temp))
+            }
+            None => Ok(Spilled::Memory(self.buffer)),
+        }
+    }
+}
+
+impl Write for SpillWriter<'_> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.disk.is_none() && (self.buffer.len() + data.len()) as u64 > self.threshold {
+            std::fs::create_dir_all(self.dir)?;
+            let temp = TempFile::create(self.dir)?;
+            let mut writer = BufWriter::new(temp.file()?);
+            writer.write_all(&std::mem::take(&mut self.buffer))?;
+            self.disk = Some((temp, writer));
+        }
+        match &mut self.disk {
+            Some((_, writer)) => writer.write_all(data)?,
+            None => self.buffer.extend_from_slice(data),
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.disk {
+            Some((_, writer)) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 两个已存在的目录是否位于同一文件系统（非 Unix 平台无法判断，视为相同）
+fn same_filesystem(a: &Path, b: &Path) -> io::Result<bool> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Ok(std::fs::metadata(a)?.dev() == std::fs::metadata(b)?.dev())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (a, b);
+        Ok(true)
+    }
+}
+
+/// 把文件数据写回磁盘后通知内核丢弃其页缓存
+///
+/// `POSIX_FADV_DONTNEED` 只能丢弃已写回的干净页面，因此先 `fdatasync`。
+/// 通知本身只是建议，失败时忽略。
+#[cfg(all(feature = "fadvise", target_os = "linux"))]
+fn drop_page_cache(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    file.sync_data()?;
+    // SAFETY: fd 在 `file` 存活期间有效，posix_fadvise 不读写用户内存
+    let ret = unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+    if ret != 0 {
+        log::debug!("posix_fadvise 失败: {}", io::Error::from_raw_os_error(ret));
+    }
+    Ok(())
+}
+
+#[cfg(not(all(feature = "fadvise", target_os = "linux")))]
+fn drop_page_cache(_file: &File) -> io::Result<()> {
+    Ok(())
+}
+
+/// 读取检查点文件中的 `(索引, CRC32)`，文件不存在时为空；无法解析的行（如中断时写了一半）被忽略
+fn load_checkpoint(path: &Path) -> io::Result<HashSet<(usize, u32)>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(e) => return Err(e),
+    };
+    let mut completed = HashSet::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let mut parts = line.split_whitespace();
+        if let (Some(index), Some(crc), None) = (parts.next(), parts.next(), parts.next())
+            && let (Ok(index), Ok(crc)) = (index.parse(), u32::from_str_radix(crc, 16))
+        {
+            completed.insert((index, crc));
+        }
+    }
+    Ok(completed)
+}
+
+/// 计算已有文件的 CRC32
+fn file_crc(path: &Path) -> io::Result<u32> {
+    let mut file = File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => return Ok(hasher.finalize()),
+            Ok(n) => hasher.update(&buffer[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 以默认配置解压整个归档
+///
+/// 开启严格路径检查，且不覆盖已存在的文件（记为跳过）。需要更多控制时使用 [`ZipExtractor`]。
+pub fn extract_zip<P: AsRef<Path>, Q: AsRef<Path>>(
+    zip_path: P,
+    output_dir: Q,
+) -> Result<ExtractionReport, DecodeError> {
+    ZipExtractor::new(zip_path, output_dir)
+        .strict_paths(true)
+        .overwrite(OverwritePolicy::Skip)
+        .extract()
+}
+
+/// 打开归档；无法识别时区分“不是 ZIP”、被截断与其他格式错误
+fn open_archive(source: &dyn ArchiveSource) -> Result<ZipArchive<Box<dyn ReadSeek>>, DecodeError> {
+    let mut reader = source.open()?;
+    check_complete(&mut reader)?;
+    match ZipArchive::new(reader) {
+        Ok(archive) => {
+            if archive.offset() > 0 {
+                log::debug!("检测到 {} 字节的自解压前导数据", archive.offset());
+            }
+            Ok(archive)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 在解析前检查归档是否被截断，给出比解压中途失败更明确的错误
+///
+/// 找不到中央目录结束记录时，以本地文件头开头的输入视为被截断的 ZIP，其余视为不是 ZIP；
+/// 找到记录但声明的中央目录超出记录位置时同样视为被截断。
+fn check_complete<R: Read + Seek>(reader: &mut R) -> Result<(), DecodeError> {
+    let size = reader.seek(SeekFrom::End(0))?;
+    match EndOfCentralDirectory::find(reader)? {
+        Some(eocd) if eocd.central_directory_fits() => Ok(()),
+        Some(_) => Err(DecodeError::TruncatedArchive { size }),
+        None => {
+            let mut signature = [0u8; 4];
+            reader.seek(SeekFrom::Start(0))?;
+            let starts_like_zip = reader.read_exact(&mut signature).is_ok()
+                && u32::from_le_bytes(signature) == eocd::LOCAL_HEADER_SIGNATURE;
+            if starts_like_zip {
+                Err(DecodeError::TruncatedArchive { size })
+            } else {
+                Err(DecodeError::NotAnArchive)
+            }
+        }
+    }
+}
+
+/// Windows 传统路径长度上限
+#[cfg(windows)]
+const MAX_PATH: usize = 260;
+
+/// 为绝对路径加上 Win32 长路径前缀（UNC 路径使用 `\\?\UNC\` 形式）
+#[cfg(any(windows, test))]
+fn long_path_prefix(absolute: &str) -> String {
+    let absolute = absolute.replace('/', "\\");
+    if absolute.starts_with(r"\\?\") {
+        absolute
+    } else if let Some(unc) = absolute.strip_prefix(r"\\") {
+        format!(r"\\?\UNC\{}", unc)
+    } else {
+        format!(r"\\?\{}", absolute)
+    }
+}
+
+/// 是否为 zip 内部 CRC 检查产生的错误（见 `zip::crc32::Crc32Reader`）
+///
+/// zip 没有公开该错误的类型，只能比较消息文本；因此 `Cargo.toml` 中固定了 zip 的版本，
+/// 升级时由 `test_zip_checksum_error_is_recognized` 检查消息是否变化。
+fn is_checksum_error(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::Other && e.to_string() == "Invalid checksum"
+}
+
+/// 条目名是否包含 `..` 路径组件（同时按 `/` 与 `\` 切分）
+fn is_traversal(name: &str) -> bool {
+    name.split(['/', '\\']).any(|comp| comp == "..")
+}
+
+/// 条目是否为符号链接（中央目录中的 Unix 文件类型为 `S_IFLNK`）
+fn is_symlink(file: &ZipFile<'_>) -> bool {
+    file.unix_mode()
+        .is_some_and(|mode| mode & 0o170000 == 0o120000)
+}
+
+/// 是否为 macOS 生成的元数据条目
+fn is_mac_metadata(name: &str) -> bool {
+    let mut components = name.split('/').filter(|comp| !comp.is_empty());
+    let is_junk_file = |comp: &str| comp == ".DS_Store" || comp.starts_with("._");
+    components.clone().any(|comp| comp == "__MACOSX")
+        || components.next_back().is_some_and(is_junk_file)
+}
+
+/// 折叠条目路径：Unicode NFC 规范化后转小写，用于冲突检测
+fn fold_name(path: &Path) -> String {
+    path.to_string_lossy()
+        .nfc()
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// 在工作线程间共享的进度计数器
+struct ProgressTracker {
+    callback: Arc<ProgressFn>,
+    bytes_done: AtomicU64,
+    bytes_total: u64,
+    interval: u64,
+}
+
+impl ProgressTracker {
+    fn new(callback: Arc<ProgressFn>, bytes_total: u64, interval: u64) -> Self {
+        Self {
+            callback,
+            bytes_done: AtomicU64::new(0),
+            bytes_total,
+            interval,
+        }
+    }
+
+    fn advance(&self, bytes: u64) {
+        let bytes_done = self.bytes_done.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        (self.callback)(Progress {
+            bytes_done,
+            bytes_total: self.bytes_total,
+        });
+    }
+}
+
+/// 按内容检测 MIME 类型时读取的前缀长度
+const SNIFF_LEN: u64 = 8 * 1024;
+
+/// 解压条目开头的一段数据并检测 MIME 类型，无法识别时返回 `None`
+fn sniff_mime(
+    archive: &mut ZipArchive<Box<dyn ReadSeek>>,
+    index: usize,
+) -> Result<Option<&'static str>, DecodeError> {
+    let mut head = Vec::with_capacity(SNIFF_LEN as usize);
+    archive
+        .by_index(index)?
+        .take(SNIFF_LEN)
+        .read_to_end(&mut head)?;
+    Ok(infer::get(&head).map(|kind| kind.mime_type()))
+}
+
+/// [`ConflictResolution::Rename`] 的目标路径：相对路径相对于冲突文件所在目录，绝对路径原样使用
+fn renamed_path(existing: &Path, target: PathBuf) -> Result<PathBuf, DecodeError> {
+    let dir = existing.parent().unwrap_or(Path::new(""));
+    if target.is_absolute() {
+        return Ok(target);
+    }
+    if target.as_os_str().is_empty()
+        || target
+            .components()
+            .any(|c| c == std::path::Component::ParentDir)
+    {
+        let name = target.to_string_lossy();
+        return Err(unsafe_path(&name, dir, &name));
+    }
+    Ok(dir.join(target))
+}
+
+/// 条目是否应覆盖 `target`：目标不存在、条目没有时间或条目时间晚于目标的修改时间
+fn is_newer(file: &ZipFile<'_>, target: &Path) -> io::Result<bool> {
+    let modified = match std::fs::metadata(target) {
+        Ok(metadata) => metadata.modified()?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+        Err(e) => return Err(e),
+    };
+    Ok(match entry_mtime(file.last_modified()) {
+        Some(entry) => entry > modified,
+        None => true,
+    })
+}
+
+/// `target` 是否已是与条目内容相同的文件：大小一致且 CRC32 与中央目录记录的相同
+fn is_unchanged(file: &ZipFile<'_>, target: &Path) -> io::Result<bool> {
+    match std::fs::metadata(target) {
+        Ok(metadata) if metadata.is_file() && metadata.len() == file.size() => {
+            Ok(file_crc(target)? == file.crc32())
+        }
+        Ok(_) => Ok(false),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// 在 `out_path` 旁写出条目元数据文件 `<文件名>.meta.json`
+fn write_sidecar(file: &ZipFile<'_>, out_path: &Path) -> io::Result<()> {
+    let time = file.last_modified();
+    let mtime = match (
+        time.year(),
+        time.month(),
+        time.day(),
+        time.hour(),
+        time.minute(),
+        time.second(),
+    ) {
+        (1980, 1, 1, 0, 0, 0) => "null".to_string(),
+        (year, month, day, hour, minute, second) => format!(
+            "\"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}\"",
+            year, month, day, hour, minute, second
+        ),
+    };
+    let fields = [
+        ("name", json_string(file.name())),
+        ("size", file.size().to_string()),
+        ("compressed_size", file.compressed_size().to_string()),
+        ("crc32", format!("\"{:08x}\"", file.crc32())),
+        ("mtime", mtime),
+        ("comment", json_string(file.comment())),
+        ("compression", json_string(&file.compression().to_string())),
+    ];
+    let body: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("  \"{}\": {}", key, value))
+        .collect();
+    let mut path = out_path.as_os_str().to_owned();
+    path.push(".meta.json");
+    std::fs::write(path, format!("{{\n{}\n}}\n", body.join(",\n")))
+}
+
+/// 把字符串编码为 JSON 字符串字面量
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// 把条目的 MS-DOS 时间按 UTC 转为 `SystemTime`
+///
+/// 未记录时间（1980-01-01 00:00:00）时为 `None`。
+fn entry_mtime(time: zip::DateTime) -> Option<SystemTime> {
+    let (year, month, day) = (time.year() as i64, time.month() as i64, time.day() as i64);
+    if (year, month, day, time.hour(), time.minute(), time.second()) == (1980, 1, 1, 0, 0, 0) {
+        return None;
+    }
+    // 公历日期到 1970-01-01 起的天数（Howard Hinnant 的 days_from_civil）
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let secs = days * 86_400
+        + time.hour() as i64 * 3600
+        + time.minute() as i64 * 60
+        + time.second() as i64;
+    Some(UNIX_EPOCH + Duration::from_secs(secs as u64))
+}
+
+/// 取出 panic 携带的信息（`panic!` 的格式化字符串或字面量）
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "未知 panic".to_string()
+    }
+}
+
+/// 各压缩方法的典型单线程解压速度（字节/秒，以解压后大小计）
+fn decompress_speed(method: CompressionMethod) -> u64 {
+    const MB: u64 = 1024 * 1024;
+    match method {
+        CompressionMethod::Stored => 1000 * MB,
+        CompressionMethod::Deflated => 300 * MB,
+        CompressionMethod::Zstd => 800 * MB,
+        CompressionMethod::Bzip2 => 30 * MB,
+        _ => 100 * MB,
+    }
+}
+
+/// 构造 `name`（相对 `base` 检查）被拒绝时的错误，`entry` 为报告给调用方的原始名称
+fn unsafe_path(entry: &str, base: &Path, name: &str) -> DecodeError {
+    let prefix = absolute_prefix_len(name);
+    let component = if prefix > 0 {
+        name[..prefix].to_string()
+    } else if is_traversal(name) {
+        "..".to_string()
+    } else {
+        String::new()
+    };
+    let mut resolved = if prefix > 0 {
+        PathBuf::from(&name[..prefix])
+    } else {
+        base.to_path_buf()
+    };
+    for comp in name[prefix..].split(['/', '\\']) {
+        match comp {
+            "" | "." => {}
+            ".." => {
+                resolved.pop();
+            }
+            comp => resolved.push(comp),
+        }
+    }
+    DecodeError::UnsafePath(UnsafePathError {
+        entry: entry.to_string(),
+        component,
+        resolved,
+    })
+}
+
+/// 条目名中表示绝对路径的前缀长度：开头的 `/`、`\\` 以及 `C:` 形式的盘符，相对路径为 0
+fn absolute_prefix_len(name: &str) -> usize {
+    let bytes = name.as_bytes();
+    let drive = if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        2
+    } else {
+        0
+    };
+    let separators = name[drive..]
+        .bytes()
+        .take_while(|b| matches!(b, b'/' | b'\\'))
+        .count();
+    // `C:` 之后不跟分隔符时是相对于该盘当前目录的路径，在 Windows 上同样会逃出输出目录
+    drive + separators
+}
+
+/// 把条目名转换为相对路径：剔除空组件与 `..`
+fn sanitize_name(name: &str) -> PathBuf {
+    name.split('/')
+        .filter(|s| !s.is_empty() && *s != "..")
+        .fold(PathBuf::new(), |mut path, comp| {
+            path.push(comp);
+            path
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Cursor;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, AtomicUsize};
+    use tempfile::tempdir;
+    use zip::write::{FileOptions, ZipWriter};
+
+    /// 在 `dir` 下写出一个包含给定条目的 zip
+    fn write_zip(dir: &Path, entries: &[(&str, &[u8])]) -> PathBuf {
+        let zip_path = dir.join("test.zip");
+        let mut writer = ZipWriter::new(File::create(&zip_path).unwrap());
+        for (name, data) in entries {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+        zip_path
+    }
+
+    #[test]
+    fn test_progress_reports_bytes() {
+        let dir = tempdir().unwrap();
+        let big = vec![7u8; 300 * 1024];
+        let zip_path = write_zip(dir.path(), &[("big.bin", &big), ("small.txt", b"hello")]);
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let out = dir.path().join("out");
+        ZipExtractor::new(&zip_path, &out)
+            .worker_threads(1)
+            .progress_interval(64 * 1024)
+            .on_progress(move |p| sink.lock().unwrap().push(p))
+            .extract()
+            .unwrap();
+
+        let seen = seen.lock().unwrap();
+        let total = big.len() as u64 + 5;
+        // 大文件在拷贝过程中应多次回调，而不是每个条目一次
+        assert!(seen.len() > 2);
+        assert!(seen.windows(2).all(|w| w[0].bytes_done < w[1].bytes_done));
+        assert!(seen.iter().all(|p| p.bytes_total == total));
+        assert_eq!(seen.last().unwrap().bytes_done, total);
+    }
+
+    #[test]
+    fn test_report_counts() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("a.txt", b"abc"), ("sub/b.txt", b"de")]);
+
+        let report = ZipExtractor::new(&zip_path, dir.path().join("out"))
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(report.bytes_written, 5);
+        assert!(report.compressed_bytes > 0);
+        assert!(report.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_empty_entries() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("empty.zip");
+        let mut writer = ZipWriter::new(File::create(&zip_path).unwrap());
+        writer
+            .add_directory("folder/", FileOptions::default())
+            .unwrap();
+        writer
+            .start_file("folder/empty.txt", FileOptions::default())
+            .unwrap();
+        writer
+            .start_file("full.txt", FileOptions::default())
+            .unwrap();
+        writer.write_all(b"x").unwrap();
+        writer.finish().unwrap();
+
+        let out = dir.path().join("out");
+        let report = ZipExtractor::new(&zip_path, &out).extract().unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(report.empty_files, 1);
+        assert_eq!(report.dirs_created, 1);
+        assert!(report.skipped.is_empty());
+        assert_eq!(fs::metadata(out.join("folder/empty.txt")).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_case_collision_policies() {
+        let dir = tempdir().unwrap();
+        // "é" 分别以预组合 (NFC) 与组合字符 (NFD) 形式出现
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("README", b"first"),
+                ("readme", b"second"),
+                ("caf\u{e9}.txt", b"nfc"),
+                ("cafe\u{301}.txt", b"nfd"),
+            ],
+        );
+
+        let out = dir.path().join("skip");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .case_collisions(CollisionPolicy::Skip)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(
+            report.skipped,
+            vec![
+                (
+                    "readme".to_string(),
+                    SkipReason::CaseCollision("README".to_string())
+                ),
+                (
+                    "cafe\u{301}.txt".to_string(),
+                    SkipReason::CaseCollision("caf\u{e9}.txt".to_string())
+                ),
+            ]
+        );
+        assert_eq!(fs::read_to_string(out.join("README")).unwrap(), "first");
+
+        let out = dir.path().join("error");
+        let err = ZipExtractor::new(&zip_path, &out)
+            .case_collisions(CollisionPolicy::Error)
+            .extract()
+            .unwrap_err();
+        assert!(matches!(err, DecodeError::CaseCollision { .. }));
+        assert!(!out.exists());
+    }
+
+    #[test]
+    fn test_extract_zip_defaults() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("a.txt", b"new"), ("b.txt", b"new")]);
+        let out = dir.path().join("out");
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("a.txt"), "old").unwrap();
+
+        let report = extract_zip(&zip_path, &out).unwrap();
+        assert_eq!(report.files_extracted, 1);
+        assert_eq!(
+            report.skipped,
+            vec![("a.txt".to_string(), SkipReason::AlreadyExists)]
+        );
+        assert_eq!(fs::read_to_string(out.join("a.txt")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(out.join("b.txt")).unwrap(), "new");
+
+        // 顺序与并行解压都报告冲突
+        for threads in [1, 2] {
+            let out = dir.path().join(format!("out{}", threads));
+            fs::create_dir_all(&out).unwrap();
+            fs::write(out.join("a.txt"), "old").unwrap();
+            let err = ZipExtractor::new(&zip_path, &out)
+                .overwrite(OverwritePolicy::Error)
+                .worker_threads(threads)
+                .extract()
+                .unwrap_err();
+            assert!(matches!(err, DecodeError::AlreadyExists(p) if p == out.join("a.txt")));
+        }
+    }
+
+    #[test]
+    fn test_output_modes() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("log.txt", b"new")]);
+        let out = dir.path().join("out");
+        fs::create_dir_all(&out).unwrap();
+        let log = out.join("log.txt");
+
+        fs::write(&log, "old-").unwrap();
+        ZipExtractor::new(&zip_path, &out)
+            .output_mode(OutputMode::Append)
+            .extract()
+            .unwrap();
+        assert_eq!(fs::read_to_string(&log).unwrap(), "old-new");
+
+        fs::write(&log, "0123456789").unwrap();
+        ZipExtractor::new(&zip_path, &out)
+            .output_mode(OutputMode::At(2))
+            .extract()
+            .unwrap();
+        assert_eq!(fs::read_to_string(&log).unwrap(), "01new56789");
+
+        ZipExtractor::new(&zip_path, &out).extract().unwrap();
+        assert_eq!(fs::read_to_string(&log).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_strict_paths_rejects_traversal() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("ok.txt", b"1"), ("../evil.txt", b"2")]);
+        let out = dir.path().join("out");
+
+        let err = extract_zip(&zip_path, &out).unwrap_err();
+        let DecodeError::UnsafePath(err) = err else {
+            panic!("{:?}", err);
+        };
+        assert_eq!(err.entry, "../evil.txt");
+        assert_eq!(err.component, "..");
+        assert_eq!(err.resolved, dir.path().join("evil.txt"));
+        assert!(!out.join("ok.txt").exists());
+
+        // 非严格模式保持原有行为：剔除 `..` 后解压到输出目录内
+        ZipExtractor::new(&zip_path, &out).extract().unwrap();
+        assert!(out.join("evil.txt").exists());
+        assert!(!dir.path().join("evil.txt").exists());
+    }
+
+    /// 手工构造只含一个 ZIP64 条目的归档：头部大小为 `0xFFFFFFFF`，真实大小在扩展字段中
+    ///
+    /// 条目数据只有几个字节，只用于检查中央目录的解析，不能真正解压。
+    fn zip64_entry_zip(name: &str, size: u64) -> Vec<u8> {
+        let data = [0u8; 5];
+        let mut extra = Vec::new();
+        extra.extend_from_slice(&0x0001u16.to_le_bytes());
+        extra.extend_from_slice(&16u16.to_le_bytes());
+        extra.extend_from_slice(&size.to_le_bytes());
+        extra.extend_from_slice(&(data.len() as u64).to_le_bytes());
+
+        let mut out = Vec::new();
+        // 本地文件头
+        out.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        out.extend_from_slice(&45u16.to_le_bytes()); // version needed
+        out.extend_from_slice(&0u16.to_le_bytes()); // flags
+        out.extend_from_slice(&8u16.to_le_bytes()); // deflate
+        out.extend_from_slice(&[0; 8]); // time/date/crc
+        out.extend_from_slice(&u32::MAX.to_le_bytes());
+        out.extend_from_slice(&u32::MAX.to_le_bytes());
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&extra);
+        out.extend_from_slice(&data);
+        // 中央目录
+        let cd_offset = out.len() as u32;
+        out.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        out.extend_from_slice(&45u16.to_le_bytes()); // made by
+        out.extend_from_slice(&45u16.to_le_bytes()); // needed
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&8u16.to_le_bytes());
+        out.extend_from_slice(&[0; 8]);
+        out.extend_from_slice(&u32::MAX.to_le_bytes());
+        out.extend_from_slice(&u32::MAX.to_le_bytes());
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        out.extend_from_slice(&[0; 10]); // comment / disk / 属性
+        out.extend_from_slice(&0u32.to_le_bytes()); // 本地头偏移
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&extra);
+        let cd_size = out.len() as u32 - cd_offset;
+        // 中央目录结束记录
+        out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&cd_size.to_le_bytes());
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_zip64_entry_sizes() {
+        let size = 5 * 1024 * 1024 * 1024u64;
+        let bytes = Arc::new(zip64_entry_zip("huge.bin", size));
+        let extractor = ZipExtractor::with_source(
+            move || Ok(Cursor::new(bytes.as_ref().clone())),
+            PathBuf::from("unused"),
+        );
+
+        assert_eq!(extractor.total_uncompressed_size().unwrap(), size);
+        let info = extractor.info().unwrap();
+        assert!(info.zip64);
+        assert_eq!(info.version_needed, 45);
+        let secs = extractor.estimate_duration().unwrap().as_secs_f64();
+        assert!((secs - size as f64 / (300.0 * 1024.0 * 1024.0)).abs() < 1e-6);
+    }
+
+    /// 手工构造一个使用数据描述符的 stored 条目：本地头中 CRC 与大小均为 0
+    fn data_descriptor_zip(name: &str, data: &[u8], central_crc: u32) -> Vec<u8> {
+        let crc = crc32fast::hash(data);
+        let size = data.len() as u32;
+        let mut out = Vec::new();
+        // 本地文件头
+        out.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        out.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        out.extend_from_slice(&0x0008u16.to_le_bytes()); // flags: 数据描述符
+        out.extend_from_slice(&0u16.to_le_bytes()); // stored
+        out.extend_from_slice(&[0; 4]); // time/date
+        out.extend_from_slice(&[0; 12]); // crc / 压缩大小 / 原始大小
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+        // 数据描述符
+        out.extend_from_slice(&0x08074b50u32.to_le_bytes());
+        out.extend_from_slice(&crc.to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        // 中央目录
+        let cd_offset = out.len() as u32;
+        out.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        out.extend_from_slice(&20u16.to_le_bytes()); // made by
+        out.extend_from_slice(&20u16.to_le_bytes()); // needed
+        out.extend_from_slice(&0x0008u16.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&central_crc.to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&[0; 12]); // extra / comment / disk / 属性
+        out.extend_from_slice(&0u32.to_le_bytes()); // 本地头偏移
+        out.extend_from_slice(name.as_bytes());
+        let cd_size = out.len() as u32 - cd_offset;
+        // 中央目录结束记录
+        out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&cd_size.to_le_bytes());
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_data_descriptor_entry() {
+        let dir = tempdir().unwrap();
+        let data = b"streamed without sizes in the local header";
+        let zip_path = dir.path().join("dd.zip");
+        fs::write(
+            &zip_path,
+            data_descriptor_zip("dd.txt", data, crc32fast::hash(data)),
+        )
+        .unwrap();
+
+        let out = dir.path().join("out");
+        let report = ZipExtractor::new(&zip_path, &out).extract().unwrap();
+        assert_eq!(report.bytes_written, data.len() as u64);
+        assert_eq!(fs::read(out.join("dd.txt")).unwrap(), data);
+
+        // 总大小限制按实际字节生效
+        let err = ZipExtractor::new(&zip_path, &out)
+            .max_total_size(10)
+            .extract()
+            .unwrap_err();
+        assert!(matches!(err, DecodeError::TotalSizeExceeded { limit: 10 }));
+    }
+
+    #[test]
+    fn test_parallel_errors_abort_extraction() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..40).map(|i| format!("f{:02}.txt", i)).collect();
+        let entries: Vec<(&str, &[u8])> = names
+            .iter()
+            .map(|n| (n.as_str(), &[b'x'; 100][..]))
+            .collect();
+        let zip_path = write_zip(dir.path(), &entries);
+
+        // 自动选择的线程数与显式指定多线程时，大小限制都会让解压失败
+        for threads in [0, 4] {
+            let err = ZipExtractor::new(&zip_path, dir.path().join(format!("out{}", threads)))
+                .worker_threads(threads)
+                .max_total_size(1000)
+                .extract()
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                DecodeError::TotalSizeExceeded { limit: 1000 }
+            ));
+        }
+    }
+
+    #[test]
+    fn test_zip_checksum_error_is_recognized() {
+        // `is_checksum_error` 依赖 zip 内部 CRC 错误的消息文本，升级 zip 后由此发现变化
+        let bytes = data_descriptor_zip("bad.txt", b"payload", 0xdeadbeef);
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut data = Vec::new();
+        let err = archive
+            .by_index(0)
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap_err();
+        assert!(is_checksum_error(&err), "{:?}", err);
+        assert!(!is_checksum_error(&io::Error::other("other")));
+    }
+
+    #[test]
+    fn test_crc_mismatch() {
+        let dir = tempdir().unwrap();
+        let data = b"payload";
+        let zip_path = dir.path().join("bad.zip");
+        fs::write(&zip_path, data_descriptor_zip("bad.txt", data, 0xdeadbeef)).unwrap();
+
+        let out = dir.path().join("out");
+        let err = ZipExtractor::new(&zip_path, &out).extract().unwrap_err();
+        assert!(matches!(
+            err,
+            DecodeError::CrcMismatch { expected: 0xdeadbeef, actual, .. } if actual == crc32fast::hash(data)
+        ));
+
+        let report = ZipExtractor::new(&zip_path, &out)
+            .verify_crc(false)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 1);
+    }
+
+    #[test]
+    fn test_compression_ratio_limit() {
+        let dir = tempdir().unwrap();
+        let zeros = vec![0u8; 1024 * 1024];
+        let zip_path = write_zip(dir.path(), &[("zeros.bin", &zeros)]);
+
+        let err = ZipExtractor::new(&zip_path, dir.path().join("out"))
+            .max_compression_ratio(10.0)
+            .extract()
+            .unwrap_err();
+        assert!(matches!(err, DecodeError::CompressionRatioExceeded { .. }));
+    }
+
+    #[test]
+    fn test_max_entries() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..10).map(|i| format!("tiny{}", i)).collect();
+        let entries: Vec<(&str, &[u8])> = names.iter().map(|n| (n.as_str(), &b""[..])).collect();
+        let zip_path = write_zip(dir.path(), &entries);
+        let out = dir.path().join("out");
+
+        let err = ZipExtractor::new(&zip_path, &out)
+            .max_entries(9)
+            .extract()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            DecodeError::TooManyEntries {
+                limit: 9,
+                found: 10
+            }
+        ));
+        assert!(err.to_string().contains('9'));
+        assert!(!out.exists());
+
+        let report = ZipExtractor::new(&zip_path, &out)
+            .max_entries(10)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 10);
+    }
+
+    #[test]
+    fn test_warn_compression_ratio() {
+        let dir = tempdir().unwrap();
+        let zeros = vec![0u8; 1024 * 1024];
+        let zip_path = write_zip(dir.path(), &[("zeros.bin", &zeros), ("small.txt", b"abc")]);
+
+        let report = ZipExtractor::new(&zip_path, dir.path().join("out"))
+            .max_compression_ratio(100_000.0)
+            .warn_compression_ratio(10.0)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(report.suspicious_entries.len(), 1);
+        assert_eq!(report.suspicious_entries[0].0, "zeros.bin");
+        assert!(report.suspicious_entries[0].1 > 10.0);
+    }
+
+    #[test]
+    fn test_compression_ratio_report() {
+        let dir = tempdir().unwrap();
+        let text = "compressible text ".repeat(5000);
+        let zip_path = write_zip(dir.path(), &[("text.txt", text.as_bytes())]);
+
+        let report = ZipExtractor::new(&zip_path, dir.path().join("out"))
+            .extract()
+            .unwrap();
+        assert!(report.compressed_bytes > 0);
+        assert!(report.compressed_bytes < report.bytes_written);
+        assert!(report.compression_ratio().unwrap() > 10.0);
+        assert_eq!(ExtractionReport::default().compression_ratio(), None);
+    }
+
+    #[test]
+    fn test_copy_buffer_is_reused() {
+        let first = PooledBuffer::with_len(COPY_BUFFER_SIZE);
+        let ptr = first.0.as_ptr();
+        // 借出期间再借会拿到独立的缓冲区
+        let nested = PooledBuffer::with_len(COPY_BUFFER_SIZE);
+        assert_ne!(nested.0.as_ptr(), ptr);
+        drop(nested);
+        drop(first);
+
+        let again = PooledBuffer::with_len(COPY_BUFFER_SIZE);
+        assert_eq!(again.0.as_ptr(), ptr);
+        assert_eq!(again.0.len(), COPY_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn test_ensure_dir_creates_once() {
+        let dir = tempdir().unwrap();
+        let ctx = ExtractContext::new(None);
+        let nested = dir.path().join("a/b");
+
+        ctx.ensure_dir(&nested).unwrap();
+        assert!(nested.is_dir());
+        // 第二次调用命中缓存，不会重新创建被外部删除的目录
+        fs::remove_dir(&nested).unwrap();
+        ctx.ensure_dir(&nested).unwrap();
+        assert!(!nested.exists());
+    }
+
+    #[test]
+    fn test_long_path_prefix() {
+        assert_eq!(long_path_prefix(r"C:\out\a.txt"), r"\\?\C:\out\a.txt");
+        assert_eq!(long_path_prefix("C:/out/a.txt"), r"\\?\C:\out\a.txt");
+        assert_eq!(
+            long_path_prefix(r"\\server\share\a.txt"),
+            r"\\?\UNC\server\share\a.txt"
+        );
+        assert_eq!(long_path_prefix(r"\\?\C:\a.txt"), r"\\?\C:\a.txt");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_path_too_long() {
+        let dir = tempdir().unwrap();
+        let name = format!("{}/file.txt", "d".repeat(250));
+        let zip_path = write_zip(dir.path(), &[(&name, b"deep")]);
+        let out = dir.path().join("out");
+
+        let err = ZipExtractor::new(&zip_path, &out).extract().unwrap_err();
+        assert!(matches!(err, DecodeError::PathTooLong { entry, .. } if entry == name));
+
+        let report = ZipExtractor::new(&zip_path, &out)
+            .long_paths(true)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 1);
+    }
+
+    #[test]
+    fn test_preserve_order_is_deterministic() {
+        let dir = tempdir().unwrap();
+        // 每个文件名出现 3 次，按顺序解压时最后一个版本生效
+        let entries: Vec<(String, String)> = (0..3)
+            .flat_map(|round| (0..20).map(move |i| (format!("f{}.txt", i), round.to_string())))
+            .collect();
+        let refs: Vec<(&str, &[u8])> = entries
+            .iter()
+            .map(|(n, d)| (n.as_str(), d.as_bytes()))
+            .collect();
+        let zip_path = write_zip(dir.path(), &refs);
+
+        for run in 0..5 {
+            let out = dir.path().join(format!("out{}", run));
+            let report = ZipExtractor::new(&zip_path, &out)
+                .worker_threads(8)
+                .preserve_order(true)
+                .extract()
+                .unwrap();
+            assert_eq!(report.files_extracted, 60);
+            for i in 0..20 {
+                assert_eq!(
+                    fs::read_to_string(out.join(format!("f{}.txt", i))).unwrap(),
+                    "2"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_extract_here() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir(&src).unwrap();
+        let zip_with_keep = dir.path().join("keep.zip");
+        fs::rename(write_zip(&src, &[("keep.txt", b"theirs")]), &zip_with_keep).unwrap();
+        let zip_path = write_zip(dir.path(), &[("here.txt", b"here")]);
+        fs::write(dir.path().join("keep.txt"), "mine").unwrap();
+
+        let report = ZipExtractor::extract_here(&zip_path).extract().unwrap();
+        assert_eq!(report.files_extracted, 1);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("here.txt")).unwrap(),
+            "here"
+        );
+
+        // 默认不覆盖旁边已有的文件
+        let report = ZipExtractor::extract_here(&zip_with_keep)
+            .extract()
+            .unwrap();
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("keep.txt")).unwrap(),
+            "mine"
+        );
+    }
+
+    #[test]
+    fn test_mixed_path_types() {
+        let dir = tempdir().unwrap();
+        let zip_path: PathBuf = write_zip(dir.path(), &[("m.txt", b"m")]);
+        let out_str: String = dir.path().join("a").to_string_lossy().into_owned();
+        let out_path: &Path = &dir.path().join("b");
+
+        ZipExtractor::new(zip_path.clone(), out_str.as_str())
+            .extract()
+            .unwrap();
+        ZipExtractor::new(zip_path.as_path(), out_path)
+            .extract()
+            .unwrap();
+        extract_zip(zip_path.to_str().unwrap(), dir.path().join("c")).unwrap();
+        ZipExtractor::with_source(FileSource::new(&zip_path), out_str.clone() + "d")
+            .extract()
+            .unwrap();
+
+        for out in [
+            PathBuf::from(&out_str),
+            out_path.to_path_buf(),
+            dir.path().join("c"),
+            PathBuf::from(out_str + "d"),
+        ] {
+            assert_eq!(fs::read_to_string(out.join("m.txt")).unwrap(), "m");
+        }
+    }
+
+    #[test]
+    fn test_into_events() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("a.txt", b"a"), ("b.txt", b"b")]);
+        let out = dir.path().join("out");
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("b.txt"), "old").unwrap();
+
+        let events: Vec<ExtractEvent> = ZipExtractor::new(&zip_path, &out)
+            .overwrite(OverwritePolicy::Skip)
+            .into_events()
+            .collect();
+        assert_eq!(events.len(), 4);
+        assert!(matches!(events[0], ExtractEvent::Started));
+        assert!(
+            matches!(&events[1], ExtractEvent::EntryExtracted(info) if info.name == "a.txt" && info.size == 1)
+        );
+        assert!(matches!(
+            &events[2],
+            ExtractEvent::EntrySkipped(name, SkipReason::AlreadyExists) if name == "b.txt"
+        ));
+        assert!(
+            matches!(&events[3], ExtractEvent::Finished(report) if report.files_extracted == 1)
+        );
+
+        let last = ZipExtractor::new(dir.path().join("missing.zip"), &out)
+            .into_events()
+            .last();
+        assert!(matches!(
+            last,
+            Some(ExtractEvent::Failed(DecodeError::Io(_)))
+        ));
+    }
+
+    #[test]
+    fn test_archive_integrity() {
+        let dir = tempdir().unwrap();
+        let text = "integrity ".repeat(2000);
+        let zip_path = write_zip(
+            dir.path(),
+            &[("ok.txt", b"ok"), ("big.txt", text.as_bytes())],
+        );
+        let out = dir.path().join("out");
+        ZipExtractor::new(&zip_path, &out).test_archive().unwrap();
+        assert!(!out.exists());
+
+        // 中央目录 CRC 被篡改
+        let bad_crc = dir.path().join("bad_crc.zip");
+        fs::write(&bad_crc, data_descriptor_zip("x.txt", b"data", 1)).unwrap();
+        let err = ZipExtractor::new(&bad_crc, &out)
+            .verify_crc(false)
+            .test_archive()
+            .unwrap_err();
+        assert!(matches!(err, DecodeError::CrcMismatch { entry, .. } if entry == "x.txt"));
+
+        // 压缩流中间被破坏
+        let mut bytes = fs::read(&zip_path).unwrap();
+        // 第一次出现的条目名位于本地头中，其后紧跟压缩数据
+        let start = bytes.windows(7).position(|w| w == b"big.txt").unwrap() + 7 + 4;
+        for b in &mut bytes[start..start + 10] {
+            *b ^= 0xff;
+        }
+        let broken = dir.path().join("broken.zip");
+        fs::write(&broken, bytes).unwrap();
+        let err = ZipExtractor::new(&broken, &out).test_archive().unwrap_err();
+        assert!(matches!(
+            err,
+            DecodeError::CorruptEntry { entry, .. } | DecodeError::CrcMismatch { entry, .. }
+                if entry == "big.txt"
+        ));
+    }
+
+    #[test]
+    fn test_archive_parallel() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..20).map(|i| format!("{:02}.txt", i)).collect();
+        let contents: Vec<String> = names.iter().map(|n| n.repeat(500)).collect();
+        let entries: Vec<(&str, &[u8])> = names
+            .iter()
+            .zip(&contents)
+            .map(|(n, c)| (n.as_str(), c.as_bytes()))
+            .collect();
+        let zip_path = write_zip(dir.path(), &entries);
+        let out = dir.path().join("out");
+        let failures = ZipExtractor::new(&zip_path, &out)
+            .test_archive_parallel(4)
+            .unwrap();
+        assert!(failures.is_empty());
+        assert!(!out.exists());
+
+        // 破坏两个条目的压缩数据，两个都应报告
+        let mut bytes = fs::read(&zip_path).unwrap();
+        for name in ["03.txt", "17.txt"] {
+            let start = bytes.windows(6).position(|w| w == name.as_bytes()).unwrap() + 6 + 4;
+            for b in &mut bytes[start..start + 10] {
+                *b ^= 0xff;
+            }
+        }
+        let broken = dir.path().join("broken.zip");
+        fs::write(&broken, bytes).unwrap();
+        let failures = ZipExtractor::new(&broken, &out)
+            .test_archive_parallel(4)
+            .unwrap();
+        let names: Vec<&str> = failures.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["03.txt", "17.txt"]);
+        assert!(ZipExtractor::new(&broken, &out).test_archive().is_err());
+
+        let missing = ZipExtractor::new(dir.path().join("missing.zip"), &out);
+        assert!(missing.test_archive_parallel(2).is_err());
+    }
+
+    #[test]
+    fn test_route_prefix() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("images/a.png", b"a"),
+                ("images/sub/b.png", b"b"),
+                ("docs/readme.md", b"r"),
+                ("other.txt", b"o"),
+                ("imagesx/c.png", b"c"),
+            ],
+        );
+        let img = dir.path().join("img");
+        let doc = dir.path().join("doc");
+        let out = dir.path().join("out");
+
+        let report = ZipExtractor::new(&zip_path, &out)
+            .route_prefix("images/", &img)
+            .route_prefix("docs", &doc)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 5);
+        assert_eq!(fs::read_to_string(img.join("a.png")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(img.join("sub/b.png")).unwrap(), "b");
+        assert_eq!(fs::read_to_string(doc.join("readme.md")).unwrap(), "r");
+        assert_eq!(fs::read_to_string(out.join("other.txt")).unwrap(), "o");
+        assert!(out.join("imagesx/c.png").exists());
+
+        let out = dir.path().join("skipped");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .route_prefix("docs", dir.path().join("doc2"))
+            .skip_unrouted(true)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 1);
+        assert_eq!(report.skipped.len(), 4);
+        assert!(
+            report
+                .skipped
+                .iter()
+                .all(|(_, r)| *r == SkipReason::Unrouted)
+        );
+        assert!(!out.exists());
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[("a.txt", b"aaa"), ("b.txt", b"bbb"), ("c.txt", b"ccc")],
+        );
+        let out = dir.path().join("out");
+        let checkpoint = dir.path().join("progress.ckpt");
+
+        // 模拟中断：a 已完整写出，b 写了一半，检查点的最后一行不完整
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("a.txt"), "aaa").unwrap();
+        fs::write(out.join("b.txt"), "b").unwrap();
+        fs::write(
+            &checkpoint,
+            format!(
+                "0 {:08x}\n1 {:08x}\n2",
+                crc32fast::hash(b"aaa"),
+                crc32fast::hash(b"bbb")
+            ),
+        )
+        .unwrap();
+
+        let report = ZipExtractor::new(&zip_path, &out)
+            .checkpoint(&checkpoint)
+            .worker_threads(1)
+            .extract()
+            .unwrap();
+        assert_eq!(
+            report.skipped,
+            vec![("a.txt".to_string(), SkipReason::Resumed)]
+        );
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(fs::read_to_string(out.join("b.txt")).unwrap(), "bbb");
+
+        // 全部完成后再次运行不再写出任何文件
+        let report = ZipExtractor::new(&zip_path, &out)
+            .checkpoint(&checkpoint)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 0);
+        assert_eq!(report.skipped.len(), 3);
+    }
+
+    #[test]
+    fn test_content_digest() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..40).map(|i| format!("d{}/f{}.txt", i % 3, i)).collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|n| (n.as_str(), n.as_bytes())).collect();
+        let zip_path = write_zip(dir.path(), &entries);
+
+        let sequential = ZipExtractor::new(&zip_path, dir.path().join("a"))
+            .preserve_order(true)
+            .extract()
+            .unwrap();
+        let parallel = ZipExtractor::new(&zip_path, dir.path().join("b"))
+            .worker_threads(4)
+            .extract()
+            .unwrap();
+        assert_eq!(sequential.content_digest().len(), 64);
+        assert_eq!(sequential.content_digest(), parallel.content_digest());
+
+        let other_dir = dir.path().join("other");
+        fs::create_dir(&other_dir).unwrap();
+        let other = write_zip(&other_dir, &entries[1..]);
+        let other = ZipExtractor::new(&other, dir.path().join("c"))
+            .extract()
+            .unwrap();
+        assert_ne!(sequential.content_digest(), other.content_digest());
+    }
+
+    #[test]
+    fn test_name_transform() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("src/main.rs", b"fn main() {}"),
+                ("__MACOSX/._main.rs", b"junk"),
+                ("old/name.txt", b"n"),
+            ],
+        );
+        let out = dir.path().join("out");
+
+        let report = ZipExtractor::new(&zip_path, &out)
+            .name_transform(|name| {
+                if name.starts_with("__MACOSX/") {
+                    None
+                } else {
+                    Some(name.replace("old/", "new/"))
+                }
+            })
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(
+            report.skipped,
+            vec![("__MACOSX/._main.rs".to_string(), SkipReason::NameRejected)]
+        );
+        assert!(out.join("src/main.rs").exists());
+        assert_eq!(fs::read_to_string(out.join("new/name.txt")).unwrap(), "n");
+        assert!(!out.join("old").exists());
+
+        // 内置的安全检查作用于转换后的名称
+        let err = ZipExtractor::new(&zip_path, &out)
+            .strict_paths(true)
+            .name_transform(|name| Some(format!("../{}", name)))
+            .extract()
+            .unwrap_err();
+        // 报告原始条目名，解析后的路径反映转换结果
+        let DecodeError::UnsafePath(err) = err else {
+            panic!("{:?}", err);
+        };
+        assert_eq!(err.entry, "src/main.rs");
+        assert_eq!(err.resolved, dir.path().join("src/main.rs"));
+    }
+
+    #[test]
+    fn test_sfx_archive() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("payload/app.txt", b"installed")]);
+        assert_eq!(
+            ZipExtractor::new(&zip_path, dir.path())
+                .sfx_stub_len()
+                .unwrap(),
+            None
+        );
+
+        // 可执行存根 + ZIP 数据
+        let mut exe = b"MZ".to_vec();
+        exe.resize(4096, 0);
+        exe.extend(fs::read(&zip_path).unwrap());
+        let exe_path = dir.path().join("setup.exe");
+        fs::write(&exe_path, &exe).unwrap();
+
+        let out = dir.path().join("out");
+        let extractor = ZipExtractor::new(&exe_path, &out);
+        assert_eq!(extractor.sfx_stub_len().unwrap(), Some(4096));
+        extractor.extract().unwrap();
+        assert_eq!(
+            fs::read_to_string(out.join("payload/app.txt")).unwrap(),
+            "installed"
+        );
+
+        // 真正的可执行文件不是 ZIP
+        let plain = dir.path().join("plain.exe");
+        fs::write(&plain, &exe[..4096]).unwrap();
+        let extractor = ZipExtractor::new(&plain, &out);
+        assert!(matches!(
+            extractor.extract(),
+            Err(DecodeError::NotAnArchive)
+        ));
+        assert!(matches!(
+            extractor.sfx_stub_len(),
+            Err(DecodeError::NotAnArchive)
+        ));
+    }
+
+    #[test]
+    fn test_symlinks_as_files() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("links.zip");
+        let mut writer = ZipWriter::new(File::create(&zip_path).unwrap());
+        writer.start_file("a.txt", FileOptions::default()).unwrap();
+        writer.write_all(b"a").unwrap();
+        writer
+            .add_symlink("link", "/etc/passwd", FileOptions::default())
+            .unwrap();
+        writer.finish().unwrap();
+
+        let out = dir.path().join("out");
+        let report = ZipExtractor::new(&zip_path, &out).extract().unwrap();
+        assert_eq!(report.files_extracted, 1);
+        assert_eq!(
+            report.skipped,
+            vec![("link".to_string(), SkipReason::Symlink)]
+        );
+        assert!(fs::symlink_metadata(out.join("link")).is_err());
+
+        let out = dir.path().join("as_files");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .symlinks_as_files(true)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        let meta = fs::symlink_metadata(out.join("link")).unwrap();
+        assert!(meta.is_file());
+        assert_eq!(fs::read_to_string(out.join("link")).unwrap(), "/etc/passwd");
+    }
+
+    #[test]
+    fn test_skip_mac_metadata() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("photos/a.jpg", b"a"),
+                ("photos/.DS_Store", b"ds"),
+                ("__MACOSX/photos/._a.jpg", b"fork"),
+                ("photos/._b.jpg", b"fork"),
+                ("photos/not_.DS_Store.txt", b"keep"),
+            ],
+        );
+
+        let out = dir.path().join("out");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .skip_mac_metadata(true)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(report.skipped.len(), 3);
+        assert!(
+            report
+                .skipped
+                .iter()
+                .all(|(_, reason)| *reason == SkipReason::MacMetadata)
+        );
+        assert!(!out.join("__MACOSX").exists());
+        assert!(out.join("photos/not_.DS_Store.txt").exists());
+
+        // 默认不过滤
+        let report = ZipExtractor::new(&zip_path, dir.path().join("all"))
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 5);
+    }
+
+    #[test]
+    fn test_archive_info() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("a.txt", b"a"), ("b.txt", b"b")]);
+        let info = ZipExtractor::new(&zip_path, dir.path()).info().unwrap();
+        assert_eq!(
+            info,
+            ArchiveInfo {
+                entries: 2,
+                version_needed: 20,
+                zip64: false,
+                encrypted: false,
+            }
+        );
+
+        // 把第二个条目的中央目录标志位改为加密
+        let mut bytes = fs::read(&zip_path).unwrap();
+        let header = bytes
+            .windows(4)
+            .rposition(|w| w == 0x02014b50u32.to_le_bytes())
+            .unwrap();
+        bytes[header + 8] |= 0x01;
+        let encrypted = dir.path().join("encrypted.zip");
+        fs::write(&encrypted, bytes).unwrap();
+        let info = ZipExtractor::new(&encrypted, dir.path()).info().unwrap();
+        assert!(info.encrypted);
+
+        // SFX 前导数据不影响读取
+        let mut sfx = vec![0u8; 300];
+        sfx.extend(fs::read(&zip_path).unwrap());
+        let sfx_path = dir.path().join("sfx.exe");
+        fs::write(&sfx_path, sfx).unwrap();
+        assert_eq!(
+            ZipExtractor::new(&sfx_path, dir.path())
+                .info()
+                .unwrap()
+                .entries,
+            2
+        );
+    }
+
+    #[test]
+    fn test_worker_panic_is_reported() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..20).map(|i| format!("f{}.txt", i)).collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|n| (n.as_str(), n.as_bytes())).collect();
+        let zip_path = write_zip(dir.path(), &entries);
+
+        let fired = Arc::new(AtomicBool::new(false));
+        let err = ZipExtractor::new(&zip_path, dir.path().join("out"))
+            .worker_threads(4)
+            .progress_interval(1)
+            .on_progress(move |_| {
+                if !fired.swap(true, Ordering::SeqCst) {
+                    panic!("injected");
+                }
+            })
+            .extract()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            DecodeError::WorkerPanicked { ref entry, ref message }
+                if names.contains(entry) && message == "injected"
+        ));
+    }
+
+    #[test]
+    fn test_worker_panic_does_not_cascade() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..40).map(|i| format!("d{}/f{}.txt", i % 3, i)).collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|n| (n.as_str(), n.as_bytes())).collect();
+        let zip_path = write_zip(dir.path(), &entries);
+        let out = dir.path().join("out");
+
+        let fired = Arc::new(AtomicBool::new(false));
+        let err = ZipExtractor::new(&zip_path, &out)
+            .worker_threads(4)
+            .checkpoint(dir.path().join("checkpoint"))
+            .progress_interval(1)
+            .on_progress(move |_| {
+                if !fired.swap(true, Ordering::SeqCst) {
+                    panic!("injected");
+                }
+            })
+            .extract()
+            .unwrap_err();
+        let DecodeError::WorkerPanicked { entry, .. } = err else {
+            panic!("unexpected error: {err}");
+        };
+
+        // 除发生 panic 的条目外，其余条目都已完整写出并记入检查点
+        for name in names.iter().filter(|name| **name != entry) {
+            assert_eq!(fs::read_to_string(out.join(name)).unwrap(), *name);
+        }
+        let checkpoint = fs::read_to_string(dir.path().join("checkpoint")).unwrap();
+        assert_eq!(checkpoint.lines().count(), names.len() - 1);
+    }
+
+    #[test]
+    fn test_persist_falls_back_to_copy() {
+        let dir = tempdir().unwrap();
+        let unsupported =
+            |_: &Path, _: &Path| Err(io::Error::from(io::ErrorKind::PermissionDenied));
+
+        // 不支持硬链接的文件系统上退回为复制
+        let temp = TempFile::create(dir.path()).unwrap();
+        fs::write(&temp.path, b"data").unwrap();
+        let temp_path = temp.path.clone();
+        let target = dir.path().join("a.txt");
+        temp.persist_with(&target, false, unsupported).unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"data");
+        assert!(!temp_path.exists());
+
+        // 目标已存在时仍然报告冲突，不复制
+        let temp = TempFile::create(dir.path()).unwrap();
+        fs::write(&temp.path, b"new").unwrap();
+        let err = temp
+            .persist_with(&target, false, |_, _| {
+                Err(io::Error::from(io::ErrorKind::AlreadyExists))
+            })
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(&target).unwrap(), b"data");
+
+        // 复制失败时不留下写了一半的目标文件（读取目录会失败）
+        let unreadable = TempFile {
+            path: dir.path().to_path_buf(),
+            persisted: true,
+        };
+        let target = dir.path().join("b.txt");
+        assert!(
+            unreadable
+                .persist_with(&target, false, unsupported)
+                .is_err()
+        );
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn test_atomic_writes() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("a.txt", b"new"), ("sub/b.txt", b"b")]);
+        let out = dir.path().join("out");
+        let temp = dir.path().join("tmp");
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("a.txt"), b"old content").unwrap();
+
+        let report = ZipExtractor::new(&zip_path, &out)
+            .atomic_writes(true)
+            .temp_dir(&temp)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(fs::read(out.join("a.txt")).unwrap(), b"new");
+        assert_eq!(fs::read(out.join("sub/b.txt")).unwrap(), b"b");
+        assert_eq!(fs::read_dir(&temp).unwrap().count(), 0);
+
+        // 不覆盖时已存在的文件保持原样，临时文件同样被清理
+        fs::write(out.join("a.txt"), b"keep").unwrap();
+        fs::remove_file(out.join("sub/b.txt")).unwrap();
+        let report = ZipExtractor::new(&zip_path, &out)
+            .atomic_writes(true)
+            .overwrite(OverwritePolicy::Skip)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 1);
+        assert_eq!(fs::read(out.join("a.txt")).unwrap(), b"keep");
+        let mut names: Vec<_> = fs::read_dir(&out)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["a.txt", "sub"]);
+    }
+
+    #[test]
+    fn test_verify_with_manifest() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("a.txt", b"abc"), ("b.txt", b"b")]);
+        let sha_abc = "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD";
+
+        let manifest = HashMap::from([("a.txt".to_string(), sha_abc.to_string())]);
+        let report = ZipExtractor::new(&zip_path, dir.path().join("ok"))
+            .verify_with(HashAlgorithm::Sha256, manifest)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+
+        let manifest = HashMap::from([("a.txt".to_string(), "0".repeat(64))]);
+        let err = ZipExtractor::new(&zip_path, dir.path().join("bad"))
+            .verify_with(HashAlgorithm::Blake3, manifest)
+            .preserve_order(true)
+            .test_archive()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            DecodeError::DigestMismatch { ref entry, algorithm: "BLAKE3", .. } if entry == "a.txt"
+        ));
+    }
+
+    #[test]
+    fn test_extract_partial_keeps_completed() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[("a.txt", b"a"), ("b.txt", b"b"), ("c.txt", b"c")],
+        );
+        let out = dir.path().join("out");
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("b.txt"), b"old").unwrap();
+
+        let partial = ZipExtractor::new(&zip_path, &out)
+            .overwrite(OverwritePolicy::Error)
+            .preserve_order(true)
+            .extract_partial()
+            .unwrap_err();
+        assert!(matches!(partial.error, DecodeError::AlreadyExists(_)));
+        assert_eq!(partial.to_string(), "解压中途失败");
+        assert_eq!(
+            std::error::Error::source(&*partial).unwrap().to_string(),
+            partial.error.to_string()
+        );
+        assert_eq!(partial.report.files_extracted, 1);
+        assert_eq!(partial.report.bytes_written, 1);
+        assert!(!out.join("c.txt").exists());
+    }
+
+    #[test]
+    fn test_extract_partial_parallel() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..40).map(|i| format!("f{:02}.txt", i)).collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|n| (n.as_str(), n.as_bytes())).collect();
+        let zip_path = write_zip(dir.path(), &entries);
+        let out = dir.path().join("out");
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("f00.txt"), b"old").unwrap();
+
+        let partial = ZipExtractor::new(&zip_path, &out)
+            .overwrite(OverwritePolicy::Error)
+            .worker_threads(4)
+            .extract_partial()
+            .unwrap_err();
+        assert!(
+            matches!(partial.error, DecodeError::AlreadyExists(ref path) if path == &out.join("f00.txt"))
+        );
+        assert_eq!(partial.report.threads_used, 4);
+        // 报告与实际写出的文件一致
+        let written = names[1..]
+            .iter()
+            .filter(|name| out.join(name).exists())
+            .count();
+        assert_eq!(partial.report.files_extracted, written);
+        assert!(written < names.len() - 1);
+        assert_eq!(fs::read(out.join("f00.txt")).unwrap(), b"old");
+    }
+
+    #[test]
+    fn test_overwrite_if_newer() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("test.zip");
+        let mut writer = ZipWriter::new(File::create(&zip_path).unwrap());
+        for (name, year) in [("old.txt", 2001), ("new.txt", 2099)] {
+            let time = zip::DateTime::from_date_and_time(year, 6, 1, 12, 0, 0).unwrap();
+            let options = FileOptions::default().last_modified_time(time);
+            writer.start_file(name, options).unwrap();
+            writer.write_all(b"archive").unwrap();
+        }
+        writer
+            .start_file(
+                "untimed.txt",
+                FileOptions::default().last_modified_time(zip::DateTime::default()),
+            )
+            .unwrap();
+        writer.write_all(b"archive").unwrap();
+        writer.finish().unwrap();
+
+        let out = dir.path().join("out");
+        fs::create_dir_all(&out).unwrap();
+        for name in ["old.txt", "new.txt", "untimed.txt"] {
+            fs::write(out.join(name), b"disk").unwrap();
+        }
+
+        let report = ZipExtractor::new(&zip_path, &out)
+            .overwrite(OverwritePolicy::IfNewer)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(
+            report.skipped,
+            vec![("old.txt".to_string(), SkipReason::NotNewer)]
+        );
+        assert_eq!(fs::read(out.join("old.txt")).unwrap(), b"disk");
+        assert_eq!(fs::read(out.join("new.txt")).unwrap(), b"archive");
+        assert_eq!(fs::read(out.join("untimed.txt")).unwrap(), b"archive");
+    }
+
+    #[test]
+    fn test_overwrite_if_different() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("same.txt", b"same"),
+                ("changed.txt", b"new!"),
+                ("longer.txt", b"abc"),
+            ],
+        );
+        let out = dir.path().join("out");
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("same.txt"), "same").unwrap();
+        fs::write(out.join("changed.txt"), "old!").unwrap();
+        fs::write(out.join("longer.txt"), "abcdef").unwrap();
+
+        let report = ZipExtractor::new(&zip_path, &out)
+            .overwrite(OverwritePolicy::IfDifferent)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(
+            report.skipped,
+            vec![("same.txt".to_string(), SkipReason::Unchanged)]
+        );
+        assert_eq!(fs::read_to_string(out.join("changed.txt")).unwrap(), "new!");
+        assert_eq!(fs::read_to_string(out.join("longer.txt")).unwrap(), "abc");
+
+        // 再次解压时所有文件都未变化
+        let report = ZipExtractor::new(&zip_path, &out)
+            .overwrite(OverwritePolicy::IfDifferent)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 0);
+        assert_eq!(report.skipped.len(), 3);
+    }
+
+    #[test]
+    fn test_entry_mtime() {
+        let time = zip::DateTime::from_date_and_time(2024, 2, 29, 23, 59, 58).unwrap();
+        assert_eq!(
+            entry_mtime(time),
+            Some(UNIX_EPOCH + Duration::from_secs(1_709_251_198))
+        );
+        assert_eq!(entry_mtime(zip::DateTime::default()), None);
+    }
+
+    #[test]
+    fn test_verify_extracted() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("a.txt", b"aaa"),
+                ("b.txt", b"bbb"),
+                ("c/d.txt", b"ddd"),
+                ("e.txt", b"e"),
+            ],
+        );
+        let extractor = ZipExtractor::new(&zip_path, dir.path().join("unused"));
+        let out = dir.path().join("out");
+        ZipExtractor::new(&zip_path, &out).extract().unwrap();
+        assert!(extractor.verify_extracted(&out).unwrap().is_empty());
+
+        fs::write(out.join("a.txt"), b"aXa").unwrap();
+        fs::write(out.join("b.txt"), b"bbbb").unwrap();
+        fs::remove_file(out.join("c/d.txt")).unwrap();
+        let mismatches = extractor.verify_extracted(&out).unwrap();
+        let kinds: Vec<_> = mismatches
+            .iter()
+            .map(|m| (m.entry.as_str(), m.kind.clone()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (
+                    "a.txt",
+                    MismatchKind::CrcDiffers {
+                        expected: crc32fast::hash(b"aaa"),
+                        actual: crc32fast::hash(b"aXa"),
+                    }
+                ),
+                (
+                    "b.txt",
+                    MismatchKind::SizeDiffers {
+                        expected: 3,
+                        actual: 4
+                    }
+                ),
+                ("c/d.txt", MismatchKind::Missing),
+            ]
+        );
+        assert_eq!(mismatches[2].path, out.join("c/d.txt"));
+    }
+
+    #[test]
+    fn test_allowed_extensions() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("sub.SRT", b"1"),
+                ("run.sh", b"2"),
+                ("Makefile", b"3"),
+                ("bin/tool.exe", b"4"),
+                ("notes.txt", b"5"),
+            ],
+        );
+        let out = dir.path().join("out");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .allowed_extensions(&[".srt", "TXT"])
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        let skipped: Vec<_> = report
+            .skipped
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(skipped, ["run.sh", "Makefile", "bin/tool.exe"]);
+        assert!(
+            report
+                .skipped
+                .iter()
+                .all(|(_, reason)| *reason == SkipReason::DisallowedExtension)
+        );
+        assert!(out.join("sub.SRT").exists());
+        assert!(!out.join("bin").exists());
+    }
+
+    #[test]
+    fn test_lowercase_names() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("Docs/README.TXT", b"first"),
+                ("docs/readme.txt", b"second"),
+                ("Img/A.PNG", b"3"),
+            ],
+        );
+        let out = dir.path().join("out");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .lowercase_names(true)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(
+            report.skipped,
+            vec![(
+                "docs/readme.txt".to_string(),
+                SkipReason::CaseCollision("Docs/README.TXT".to_string())
+            )]
+        );
+        assert_eq!(fs::read(out.join("docs/readme.txt")).unwrap(), b"first");
+        assert!(out.join("img/a.png").exists());
+    }
+
+    #[test]
+    fn test_extract_to_memory_spills_large_entries() {
+        let dir = tempdir().unwrap();
+        let big = vec![9u8; 10_000];
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("small.txt", b"tiny"),
+                ("big.bin", &big),
+                ("other.txt", b"x"),
+            ],
+        );
+        let spill = dir.path().join("spill");
+
+        let entries = ZipExtractor::new(&zip_path, dir.path())
+            .spill_threshold(1024)
+            .temp_dir(&spill)
+            .extract_to_memory()
+            .unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0],
+            (
+                "small.txt".to_string(),
+                EntryData::InMemory(b"tiny".to_vec())
+            )
+        );
+        let EntryData::OnDisk(path) = &entries[1].1 else {
+            panic!("big entry should be spilled");
+        };
+        assert!(path.starts_with(&spill));
+        assert_eq!(fs::read(path).unwrap(), big);
+        assert_eq!(entries[2].1, EntryData::InMemory(b"x".to_vec()));
+    }
+
+    #[test]
+    fn test_from_reader_at() {
+        let dir = tempdir().unwrap();
+        let zip = fs::read(write_zip(
+            dir.path(),
+            &[
+                ("a.txt", b"alpha"),
+                ("sub/b.txt", b"beta"),
+                ("c.txt", b"gamma"),
+            ],
+        ))
+        .unwrap();
+        // 归档前面是一段与 ZIP 无关的数据，其中的偏移相对于归档开头
+        let mut blob = b"GAMEDATA".repeat(100);
+        let offset = blob.len() as u64;
+        blob.extend_from_slice(&zip);
+
+        for threads in [1, 4] {
+            let out = dir.path().join(format!("out{}", threads));
+            let report = ZipExtractor::from_reader_at(Cursor::new(blob.clone()), offset, &out)
+                .unwrap()
+                .worker_threads(threads)
+                .extract()
+                .unwrap();
+            assert_eq!(report.files_extracted, 3);
+            assert_eq!(fs::read(out.join("sub/b.txt")).unwrap(), b"beta");
+        }
+
+        let err = ZipExtractor::from_reader_at(Cursor::new(blob.clone()), offset - 1, dir.path())
+            .err()
+            .unwrap();
+        assert!(matches!(err, DecodeError::NoArchiveAtOffset { offset: o } if o == offset - 1));
+        let err = ZipExtractor::from_reader_at(Cursor::new(blob), 1 << 20, dir.path())
+            .err()
+            .unwrap();
+        assert!(matches!(err, DecodeError::NoArchiveAtOffset { .. }));
+    }
+
+    #[test]
+    fn test_subdir() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("a.txt", b"a"), ("docs/b.txt", b"b")]);
+        let out = dir.path().join("out");
+
+        ZipExtractor::new(&zip_path, &out)
+            .subdir("first")
+            .extract()
+            .unwrap();
+        ZipExtractor::new(&zip_path, &out)
+            .subdir("second/copy")
+            .extract()
+            .unwrap();
+        assert_eq!(fs::read(out.join("first/a.txt")).unwrap(), b"a");
+        assert_eq!(fs::read(out.join("second/copy/docs/b.txt")).unwrap(), b"b");
+        assert!(!out.join("a.txt").exists());
+
+        for unsafe_subdir in ["../escape", "/abs", "", "a/../../b"] {
+            let err = ZipExtractor::new(&zip_path, &out)
+                .subdir(unsafe_subdir)
+                .extract()
+                .unwrap_err();
+            assert!(
+                matches!(&err, DecodeError::UnsafePath(e) if e.entry == unsafe_subdir),
+                "{:?}",
+                err
+            );
+        }
+        assert!(!dir.path().join("escape").exists());
+    }
+
+    #[test]
+    fn test_track_slowest() {
+        let dir = tempdir().unwrap();
+        let entries: Vec<(String, Vec<u8>)> = (0..30)
+            .map(|i| (format!("f{:02}.bin", i), vec![i as u8; 1000 * (i + 1)]))
+            .collect();
+        let refs: Vec<(&str, &[u8])> = entries
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect();
+        let zip_path = write_zip(dir.path(), &refs);
+
+        for threads in [1, 4] {
+            let out = dir.path().join(format!("out{}", threads));
+            let report = ZipExtractor::new(&zip_path, &out)
+                .worker_threads(threads)
+                .track_slowest(5)
+                .extract()
+                .unwrap();
+            assert_eq!(report.slowest_entries.len(), 5);
+            assert!(report.slowest_entries.windows(2).all(|w| w[0].1 >= w[1].1));
+        }
+
+        let report = ZipExtractor::new(&zip_path, dir.path().join("untracked"))
+            .extract()
+            .unwrap();
+        assert!(report.slowest_entries.is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let dir = tempdir().unwrap();
+        let v1 = dir.path().join("v1.zip");
+        let v2 = dir.path().join("v2.zip");
+        fs::rename(
+            write_zip(
+                dir.path(),
+                &[
+                    ("same.txt", b"same"),
+                    ("edit.txt", b"old"),
+                    ("gone.txt", b"x"),
+                ],
+            ),
+            &v1,
+        )
+        .unwrap();
+        fs::rename(
+            write_zip(
+                dir.path(),
+                &[
+                    ("new.txt", b"y"),
+                    ("edit.txt", b"new!"),
+                    ("same.txt", b"same"),
+                ],
+            ),
+            &v2,
+        )
+        .unwrap();
+        let out = dir.path().join("out");
+        let old = ZipExtractor::new(&v1, &out);
+        let new = ZipExtractor::new(&v2, &out);
+
+        let diff = old.diff(&new).unwrap();
+        let names = |entries: &[EntryInfo]| -> Vec<String> {
+            entries.iter().map(|e| e.name.clone()).collect()
+        };
+        assert_eq!(names(&diff.added), ["new.txt"]);
+        assert_eq!(names(&diff.removed), ["gone.txt"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].0.name, "edit.txt");
+        assert_eq!((diff.changed[0].0.size, diff.changed[0].1.size), (3, 4));
+        assert!(old.diff(&old).unwrap().is_empty());
+        assert!(!out.exists());
+    }
+
+    #[test]
+    fn test_prepare() {
+        let dir = tempdir().unwrap();
+        let entries: Vec<(String, Vec<u8>)> = (0..50)
+            .map(|i| {
+                (
+                    format!("data/{}.txt", i),
+                    format!("entry {}", i).into_bytes(),
+                )
+            })
+            .collect();
+        let refs: Vec<(&str, &[u8])> = entries
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect();
+        let zip_path = write_zip(dir.path(), &refs);
+
+        let extractor = ZipExtractor::new(&zip_path, dir.path());
+        let mut prepared = extractor.prepare().unwrap();
+        assert_eq!(prepared.len(), 50);
+        assert_eq!(prepared.index_of("data/7.txt"), Some(7));
+        for (name, data) in entries.iter().rev() {
+            let mut out = Vec::new();
+            prepared.extract_entry_to_writer(name, &mut out).unwrap();
+            assert_eq!(&out, data);
+        }
+
+        let mut text = String::new();
+        prepared
+            .open_entry_reader("data/42.txt")
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "entry 42");
+        assert!(matches!(
+            prepared.open_entry_reader("missing.txt"),
+            Err(DecodeError::Zip(ZipError::FileNotFound))
+        ));
+    }
+
+    #[test]
+    fn test_adaptive_buffers() {
+        let mut adaptive = AdaptiveBuffers::new((64 * 1024, 1024 * 1024));
+        let sizes: Vec<(usize, usize)> = adaptive.candidates.iter().map(|c| c.sizes).collect();
+        assert_eq!(
+            sizes,
+            [
+                (256 * 1024, 4 * 1024 * 1024),
+                (64 * 1024, 1024 * 1024),
+                (16 * 1024, 256 * 1024)
+            ]
+        );
+        for _ in 0..ADAPTIVE_SAMPLES * sizes.len() {
+            let (slot, _) = adaptive.next();
+            let slot = slot.unwrap();
+            // 第二组候选最快
+            let elapsed = Duration::from_millis(if slot == 1 { 1 } else { 10 });
+            adaptive.record(slot, 1024 * 1024, elapsed);
+        }
+        assert_eq!(adaptive.chosen, Some(sizes[1]));
+        assert_eq!(adaptive.next(), (None, sizes[1]));
+
+        let dir = tempdir().unwrap();
+        let data = vec![7u8; 200 * 1024];
+        let names: Vec<String> = (0..20).map(|i| format!("{}.bin", i)).collect();
+        let entries: Vec<(&str, &[u8])> = names.iter().map(|n| (n.as_str(), &data[..])).collect();
+        let zip_path = write_zip(dir.path(), &entries);
+        let out = dir.path().join("out");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .adaptive_buffers(true)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 20);
+        let chosen = report.buffer_sizes.unwrap();
+        assert!(
+            AdaptiveBuffers::new((COPY_BUFFER_SIZE, 4 * 1024 * 1024))
+                .candidates
+                .iter()
+                .any(|c| c.sizes == chosen)
+        );
+        assert_eq!(fs::read(out.join("19.bin")).unwrap(), data);
+
+        let report = ZipExtractor::new(&zip_path, dir.path().join("fixed"))
+            .extract()
+            .unwrap();
+        assert_eq!(report.buffer_sizes, None);
+    }
+
+    #[test]
+    fn test_buffer_sizer() {
+        let dir = tempdir().unwrap();
+        let big = (0..200_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let zip_path = write_zip(dir.path(), &[("tiny.txt", b"tiny"), ("big.bin", &big)]);
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let out = dir.path().join("out");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .buffer_sizer(move |info| {
+                sink.lock().unwrap().push(info.name.clone());
+                if info.size < 1024 {
+                    (0, 16)
+                } else {
+                    (7, 1 << 20)
+                }
+            })
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(fs::read(out.join("tiny.txt")).unwrap(), b"tiny");
+        assert_eq!(fs::read(out.join("big.bin")).unwrap(), big);
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort();
+        assert_eq!(seen, ["big.bin", "tiny.txt"]);
+    }
+
+    #[test]
+    fn test_threads_used() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..100).map(|i| format!("{}.txt", i)).collect();
+        let entries: Vec<(&str, &[u8])> = names.iter().map(|n| (n.as_str(), &b"x"[..])).collect();
+        let zip_path = write_zip(dir.path(), &entries);
+
+        let report = ZipExtractor::new(&zip_path, dir.path().join("a"))
+            .worker_threads(3)
+            .extract()
+            .unwrap();
+        assert_eq!(report.threads_used, 3);
+
+        // 5 个条目按 4 个线程分块只能分出 3 块，报告实际启动的工作线程数
+        fs::create_dir(dir.path().join("five")).unwrap();
+        let five = write_zip(
+            &dir.path().join("five"),
+            &[
+                ("1", b"1"),
+                ("2", b"2"),
+                ("3", b"3"),
+                ("4", b"4"),
+                ("5", b"5"),
+            ],
+        );
+        let report = ZipExtractor::new(&five, dir.path().join("d"))
+            .worker_threads(4)
+            .extract()
+            .unwrap();
+        assert_eq!(report.threads_used, 3);
+        assert_eq!(report.files_extracted, 5);
+
+        let report = ZipExtractor::new(&zip_path, dir.path().join("b"))
+            .worker_threads(3)
+            .sequential()
+            .extract()
+            .unwrap();
+        assert_eq!(report.threads_used, 1);
+
+        // 自动选择时按每 20 个文件一个线程估算，条目很少时退回单线程
+        fs::create_dir(dir.path().join("small")).unwrap();
+        let small = write_zip(&dir.path().join("small"), &[("a.txt", b"a")]);
+        let report = ZipExtractor::new(&small, dir.path().join("c"))
+            .extract()
+            .unwrap();
+        assert_eq!(report.threads_used, 1);
+    }
+
+    #[test]
+    fn test_sequential() {
+        let dir = tempdir().unwrap();
+        let entries: Vec<(String, Vec<u8>)> = (0..100)
+            .map(|i| (format!("{}.txt", i), vec![b'x'; 10]))
+            .collect();
+        let refs: Vec<(&str, &[u8])> = entries
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect();
+        let zip_path = write_zip(dir.path(), &refs);
+
+        let caller = thread::current().id();
+        let off_thread = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&off_thread);
+        let report = ZipExtractor::new(&zip_path, dir.path().join("out"))
+            .worker_threads(8)
+            .sequential()
+            .on_entry_start(move |_| {
+                if thread::current().id() != caller {
+                    flag.store(true, Ordering::SeqCst);
+                }
+            })
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 100);
+        assert!(!off_thread.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_entry_size_filters() {
+        let dir = tempdir().unwrap();
+        let big = vec![b'x'; 1000];
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("tiny.txt", b"a"),
+                ("mid.txt", b"0123456789"),
+                ("big.txt", &big),
+                ("sub/", b""),
+            ],
+        );
+
+        let out = dir.path().join("out");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .min_entry_size(2)
+            .max_entry_size(100)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 1);
+        let mut skipped = report.skipped.clone();
+        skipped.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            skipped,
+            vec![
+                ("big.txt".to_string(), SkipReason::SizeOutOfRange),
+                ("tiny.txt".to_string(), SkipReason::SizeOutOfRange),
+            ]
+        );
+        assert!(out.join("mid.txt").exists());
+
+        // 边界值包含在内
+        let report = ZipExtractor::new(&zip_path, dir.path().join("exact"))
+            .min_entry_size(10)
+            .max_entry_size(10)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 1);
+    }
+
+    #[test]
+    fn test_content_types() {
+        let dir = tempdir().unwrap();
+        let png = [
+            0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n', 0, 0, 0, 0x0D, b'I', b'H', b'D',
+            b'R',
+        ];
+        let pdf = b"%PDF-1.7\n%...";
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("photo", &png),
+                ("misleading.txt", &png),
+                ("doc.bin", pdf),
+                ("notes.png", b"just text"),
+                ("folder/", b""),
+            ],
+        );
+
+        let out = dir.path().join("images");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .content_types(&["image/*"])
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(fs::read(out.join("photo")).unwrap(), png);
+        assert!(out.join("misleading.txt").exists());
+        let mut skipped: Vec<_> = report.skipped.iter().map(|(n, _)| n.as_str()).collect();
+        skipped.sort();
+        assert_eq!(skipped, ["doc.bin", "notes.png"]);
+        assert!(
+            report
+                .skipped
+                .iter()
+                .all(|(_, reason)| *reason == SkipReason::ContentType)
+        );
+
+        let out = dir.path().join("pdf");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .content_types(&["application/pdf"])
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 1);
+        assert!(out.join("doc.bin").exists());
+    }
+
+    #[test]
+    fn test_tree() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("a.txt", b"aaa"),
+                ("docs/b.txt", b"bb"),
+                ("docs/sub/c.txt", b"c"),
+            ],
+        );
+        let out = dir.path().join("out");
+        let tree = ZipExtractor::new(&zip_path, &out).tree().unwrap();
+        assert_eq!(tree.size, 6);
+        assert_eq!(tree.files[0].name, "a.txt");
+        assert_eq!(tree.find("docs").unwrap().size, 3);
+        assert_eq!(tree.find("docs/sub").unwrap().files[0].index, 2);
+        assert!(!out.exists());
+    }
+
+    #[test]
+    fn test_collect_crc_errors() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("stored.zip");
+        let mut writer = ZipWriter::new(File::create(&zip_path).unwrap());
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        for (name, data) in [
+            ("a.txt", b"AAAAAAAA"),
+            ("b.txt", b"BBBBBBBB"),
+            ("c.txt", b"CCCCCCCC"),
+        ] {
+            writer.start_file(name, stored).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+
+        // 破坏 a.txt 与 c.txt 的存储数据
+        let mut bytes = fs::read(&zip_path).unwrap();
+        for pattern in [b"AAAAAAAA", b"CCCCCCCC"] {
+            let start = bytes.windows(8).position(|w| w == pattern).unwrap();
+            bytes[start] ^= 0xff;
+        }
+        fs::write(&zip_path, bytes).unwrap();
+
+        // 默认在第一个损坏的条目处中止
+        let out = dir.path().join("fail_fast");
+        let err = ZipExtractor::new(&zip_path, &out)
+            .preserve_order(true)
+            .extract()
+            .unwrap_err();
+        assert!(matches!(err, DecodeError::CrcMismatch { entry, .. } if entry == "a.txt"));
+
+        for preserve_order in [true, false] {
+            let out = dir.path().join(format!("collect_{}", preserve_order));
+            let report = ZipExtractor::new(&zip_path, &out)
+                .preserve_order(preserve_order)
+                .collect_crc_errors(true)
+                .extract()
+                .unwrap();
+            assert_eq!(report.files_extracted, 1);
+            let mut corrupt: Vec<_> = report.corrupt.iter().map(|m| m.entry.as_str()).collect();
+            corrupt.sort();
+            assert_eq!(corrupt, ["a.txt", "c.txt"]);
+            assert!(report.corrupt.iter().all(|m| matches!(
+                m.kind,
+                MismatchKind::CrcDiffers { expected, actual } if expected != actual
+            )));
+            assert_eq!(fs::read(out.join("b.txt")).unwrap(), b"BBBBBBBB");
+        }
+    }
+
+    #[test]
+    fn test_extract_decoded() {
+        let dir = tempdir().unwrap();
+        let (gbk, _, _) =
+            encoding_rs::GBK.encode("你好，世界。这是一段用于检测编码的中文文本，内容足够长。");
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("subs/a.TXT", &gbk),
+                ("notes.srt", "\u{FEFF}带 BOM 的文本".as_bytes()),
+                ("image.png", &[0x89, b'P', b'N', b'G', 0, 0xFF]),
+            ],
+        );
+
+        let entries = ZipExtractor::new(&zip_path, dir.path())
+            .extract_decoded(&["txt", ".srt"])
+            .unwrap();
+        let names: Vec<_> = entries.keys().map(String::as_str).collect();
+        assert_eq!(names, ["image.png", "notes.srt", "subs/a.TXT"]);
+        let DecodedEntry::Text(gbk) = &entries["subs/a.TXT"] else {
+            panic!("txt should be decoded");
+        };
+        assert_eq!(gbk.encoding.name(), "GBK");
+        assert!(gbk.text.starts_with("你好，世界。"));
+        let DecodedEntry::Text(notes) = &entries["notes.srt"] else {
+            panic!("srt should be decoded");
+        };
+        assert_eq!(notes.text, "带 BOM 的文本");
+        assert_eq!(
+            entries["image.png"],
+            DecodedEntry::Binary(EntryData::InMemory(vec![0x89, b'P', b'N', b'G', 0, 0xFF]))
+        );
+        // 不写出任何文件
+        assert!(!dir.path().join("notes.srt").exists());
+    }
+
+    #[cfg(feature = "fadvise")]
+    #[test]
+    fn test_drop_page_cache() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("a.txt", b"aaa"), ("b/c.txt", b"ccc")]);
+
+        for atomic in [false, true] {
+            let out = dir.path().join(format!("out-{}", atomic));
+            let report = ZipExtractor::new(&zip_path, &out)
+                .drop_page_cache(true)
+                .atomic_writes(atomic)
+                .extract()
+                .unwrap();
+            assert_eq!(report.files_extracted, 2);
+            assert_eq!(fs::read_to_string(out.join("a.txt")).unwrap(), "aaa");
+            assert_eq!(fs::read_to_string(out.join("b/c.txt")).unwrap(), "ccc");
+        }
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_output() {
+        use flate2::read::GzDecoder;
+
+        let dir = tempdir().unwrap();
+        let text = "gzip output ".repeat(1000);
+        let zip_path = write_zip(
+            dir.path(),
+            &[("Docs/A.TXT", text.as_bytes()), ("empty/", b"")],
+        );
+
+        for atomic in [false, true] {
+            let out = dir.path().join(format!("out-{}", atomic));
+            let report = ZipExtractor::new(&zip_path, &out)
+                .gzip_output(true)
+                .lowercase_names(true)
+                .atomic_writes(atomic)
+                .extract()
+                .unwrap();
+            assert_eq!(report.bytes_written, text.len() as u64);
+            assert!(out.join("empty").is_dir());
+            assert!(!out.join("docs/a.txt").exists());
+
+            let gz = out.join("docs/a.txt.gz");
+            assert!(fs::metadata(&gz).unwrap().len() < text.len() as u64);
+            let mut decoded = String::new();
+            GzDecoder::new(File::open(&gz).unwrap())
+                .read_to_string(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, text);
+        }
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_from_url() {
+        use std::net::TcpListener;
+
+        let dir = tempdir().unwrap();
+        let bytes = fs::read(write_zip(dir.path(), &[("a.txt", b"remote")])).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/release.zip", listener.local_addr().unwrap());
+        let len = bytes.len() as u64;
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                bytes.len()
+            )
+            .unwrap();
+            stream.write_all(&bytes).unwrap();
+        });
+
+        let out = dir.path().join("out");
+        let extractor = ZipExtractor::from_url(&url, &out).unwrap();
+        let report = extractor.extract().unwrap();
+        assert_eq!(report.downloaded_bytes, len);
+        assert_eq!(fs::read(out.join("a.txt")).unwrap(), b"remote");
+    }
+
+    #[test]
+    fn test_entry_hooks() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..30).map(|i| format!("f{}.txt", i)).collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|n| (n.as_str(), n.as_bytes())).collect();
+        let zip_path = write_zip(dir.path(), &entries);
+        let out = dir.path().join("out");
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("f0.txt"), b"existing").unwrap();
+
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let done = Arc::new(Mutex::new(Vec::new()));
+        let (started_sink, done_sink) = (Arc::clone(&started), Arc::clone(&done));
+        ZipExtractor::new(&zip_path, &out)
+            .worker_threads(4)
+            .overwrite(OverwritePolicy::Skip)
+            .on_entry_start(move |info| started_sink.lock().unwrap().push(info.name.clone()))
+            .on_entry_done(move |info, path| {
+                // 回调时文件已经完整写出
+                assert_eq!(fs::read_to_string(path).unwrap(), info.name);
+                done_sink.lock().unwrap().push(info.name.clone());
+            })
+            .extract()
+            .unwrap();
+
+        let mut started = started.lock().unwrap().clone();
+        let mut done = done.lock().unwrap().clone();
+        started.sort();
+        done.sort();
+        let mut expected = names.clone();
+        expected.sort();
+        assert_eq!(started, expected);
+        expected.retain(|name| name != "f0.txt");
+        assert_eq!(done, expected);
+    }
+
+    #[test]
+    fn test_truncated_archive() {
+        let dir = tempdir().unwrap();
+        let data = vec![5u8; 4096];
+        let zip_path = write_zip(dir.path(), &[("a.bin", &data), ("b.bin", &data)]);
+        let bytes = fs::read(&zip_path).unwrap();
+
+        // 下载中断：只剩前半部分
+        let cut = dir.path().join("cut.zip");
+        fs::write(&cut, &bytes[..bytes.len() / 2]).unwrap();
+        let err = ZipExtractor::new(&cut, dir.path().join("out"))
+            .extract()
+            .unwrap_err();
+        assert!(
+            matches!(err, DecodeError::TruncatedArchive { size } if size == bytes.len() as u64 / 2)
+        );
+
+        // 中央目录结束记录还在，但中央目录的一部分丢失
+        let mut hollow = bytes.clone();
+        let eocd = EndOfCentralDirectory::find(&mut Cursor::new(&bytes))
+            .unwrap()
+            .unwrap();
+        let cd_start = eocd.central_directory_offset as usize;
+        hollow.drain(cd_start..cd_start + 10);
+        let hollow_path = dir.path().join("hollow.zip");
+        fs::write(&hollow_path, &hollow).unwrap();
+        let err = ZipExtractor::new(&hollow_path, dir.path().join("out"))
+            .test_archive()
+            .unwrap_err();
+        assert!(matches!(err, DecodeError::TruncatedArchive { .. }));
+    }
+
+    #[test]
+    fn test_absolute_path_policies() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("/etc/passwd", b"root"),
+                ("C:\\boot.ini", b"x"),
+                ("ok.txt", b"ok"),
+            ],
+        );
+
+        let out = dir.path().join("relative");
+        let report = ZipExtractor::new(&zip_path, &out).extract().unwrap();
+        assert_eq!(report.relativized, ["/etc/passwd", "C:\\boot.ini"]);
+        assert_eq!(fs::read(out.join("etc/passwd")).unwrap(), b"root");
+
+        let report = ZipExtractor::new(&zip_path, dir.path().join("skip"))
+            .absolute_paths(AbsolutePathPolicy::Skip)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 1);
+        assert!(report.relativized.is_empty());
+        assert_eq!(
+            report.skipped,
+            vec![
+                ("/etc/passwd".to_string(), SkipReason::AbsolutePath),
+                ("C:\\boot.ini".to_string(), SkipReason::AbsolutePath),
+            ]
+        );
+
+        let err = ZipExtractor::new(&zip_path, dir.path().join("reject"))
+            .absolute_paths(AbsolutePathPolicy::Reject)
+            .extract()
+            .unwrap_err();
+        let DecodeError::UnsafePath(err) = err else {
+            panic!("{:?}", err);
+        };
+        assert_eq!(err.entry, "/etc/passwd");
+        assert_eq!(err.component, "/");
+        assert_eq!(err.resolved, Path::new("/etc/passwd"));
+        assert!(!dir.path().join("reject").exists());
+    }
+
+    #[test]
+    fn test_absolute_prefix_len() {
+        assert_eq!(absolute_prefix_len("a/b"), 0);
+        assert_eq!(absolute_prefix_len("//server/share"), 2);
+        assert_eq!(absolute_prefix_len("\\temp"), 1);
+        assert_eq!(absolute_prefix_len("C:/x"), 3);
+        assert_eq!(absolute_prefix_len("C:x"), 2);
+        assert_eq!(absolute_prefix_len("1:x"), 0);
+    }
+
+    #[test]
+    fn test_estimate_duration() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("test.zip");
+        let mut writer = ZipWriter::new(File::create(&zip_path).unwrap());
+        let data = vec![0u8; 3 * 1024 * 1024];
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        writer.start_file("stored.bin", stored).unwrap();
+        writer.write_all(&data).unwrap();
+        writer
+            .start_file("deflated.bin", FileOptions::default())
+            .unwrap();
+        writer.write_all(&data).unwrap();
+        writer.finish().unwrap();
+
+        let estimate = ZipExtractor::new(&zip_path, dir.path())
+            .estimate_duration()
+            .unwrap();
+        let expected = 3.0 / 1000.0 + 3.0 / 300.0;
+        assert!((estimate.as_secs_f64() - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_metadata_sidecars() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("meta.zip");
+        let mut writer = ZipWriter::new(File::create(&zip_path).unwrap());
+        let time = zip::DateTime::from_date_and_time(2021, 3, 4, 5, 6, 8).unwrap();
+        writer
+            .start_file(
+                "docs/a \"q\".txt",
+                FileOptions::default().last_modified_time(time),
+            )
+            .unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer
+            .start_file(
+                "b.txt",
+                FileOptions::default().compression_method(CompressionMethod::Stored),
+            )
+            .unwrap();
+        writer.write_all(b"b").unwrap();
+        writer.finish().unwrap();
+
+        // 元数据文件不计入报告，也不受总大小上限约束
+        let out = dir.path().join("out");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .metadata_sidecars(true)
+            .max_total_size(6)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(report.bytes_written, 6);
+
+        let meta = fs::read_to_string(out.join("docs/a \"q\".txt.meta.json")).unwrap();
+        assert!(meta.contains(r#""name": "docs/a \"q\".txt""#), "{}", meta);
+        assert!(meta.contains(r#""size": 5"#));
+        let crc = format!(r#""crc32": "{:08x}""#, crc32fast::hash(b"hello"));
+        assert!(meta.contains(&crc));
+        assert!(meta.contains(r#""mtime": "2021-03-04T05:06:08""#));
+        assert!(meta.contains(r#""comment": """#));
+        assert!(meta.contains(r#""compression": "Deflated""#));
+        let meta = fs::read_to_string(out.join("b.txt.meta.json")).unwrap();
+        assert!(meta.contains(r#""compression": "Stored""#), "{}", meta);
+
+        let plain = dir.path().join("plain");
+        ZipExtractor::new(&zip_path, &plain).extract().unwrap();
+        assert!(!plain.join("b.txt.meta.json").exists());
+    }
+
+    #[test]
+    fn test_dirs_only() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("test.zip");
+        let mut writer = ZipWriter::new(File::create(&zip_path).unwrap());
+        writer
+            .add_directory("empty/", FileOptions::default())
+            .unwrap();
+        writer
+            .start_file("a/b/c.txt", FileOptions::default())
+            .unwrap();
+        writer.write_all(b"content").unwrap();
+        writer
+            .start_file("top.txt", FileOptions::default())
+            .unwrap();
+        writer.finish().unwrap();
+
+        let out = dir.path().join("out");
+        let report = ZipExtractor::new(&zip_path, &out)
+            .dirs_only(true)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 0);
+        assert_eq!(report.dirs_created, 1);
+        assert_eq!(report.bytes_written, 0);
+        assert!(out.join("empty").is_dir());
+        assert!(out.join("a/b").is_dir());
+        assert!(!out.join("a/b/c.txt").exists());
+        assert!(!out.join("top.txt").exists());
+    }
+
+    #[test]
+    fn test_extract_indices() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[("a.txt", b"a"), ("b.txt", b"b"), ("c.txt", b"c")],
+        );
+        let out = dir.path().join("out");
+        let extractor = ZipExtractor::new(&zip_path, &out);
+
+        let err = extractor.extract_indices(&[0, 3, 7]).unwrap_err();
+        assert!(matches!(
+            err,
+            DecodeError::IndexOutOfRange { ref indices, len: 3 } if *indices == [3, 7]
+        ));
+        assert!(!out.exists());
+
+        let report = extractor.extract_indices(&[2, 0, 2]).unwrap();
+        assert_eq!(report.files_extracted, 2);
+        assert!(out.join("a.txt").exists());
+        assert!(!out.join("b.txt").exists());
+        assert!(out.join("c.txt").exists());
+    }
+
+    #[test]
+    fn test_entries_in_order() {
+        let dir = tempdir().unwrap();
+        let names = [
+            "META-INF/MANIFEST.MF",
+            "z.class",
+            "a/",
+            "a/b.class",
+            "M.class",
+        ];
+        let entries: Vec<(&str, &[u8])> = names.iter().map(|n| (*n, &b""[..])).collect();
+        let zip_path = write_zip(dir.path(), &entries);
+
+        let listed = ZipExtractor::new(&zip_path, dir.path())
+            .entries_in_order()
+            .unwrap();
+        assert_eq!(listed, names);
+    }
+
+    #[test]
+    fn test_extract_entry_to_writer() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("a.txt", b"alpha"), ("b/c.txt", b"gamma")]);
+        let extractor = ZipExtractor::new(&zip_path, dir.path().join("unused"));
+
+        let mut out = Vec::new();
+        assert_eq!(
+            extractor
+                .extract_entry_to_writer("b/c.txt", &mut out)
+                .unwrap(),
+            5
+        );
+        assert_eq!(out, b"gamma");
+        assert!(!dir.path().join("unused").exists());
+
+        let err = extractor
+            .extract_entry_to_writer("missing", &mut Vec::new())
+            .unwrap_err();
+        assert!(matches!(err, DecodeError::Zip(ZipError::FileNotFound)));
+
+        // 数据已被推送出去，但结尾仍报告 CRC 错误
+        let bad = dir.path().join("bad.zip");
+        fs::write(&bad, data_descriptor_zip("x.txt", b"data", 7)).unwrap();
+        let mut out = Vec::new();
+        let err = ZipExtractor::new(&bad, dir.path())
+            .extract_entry_to_writer("x.txt", &mut out)
+            .unwrap_err();
+        assert!(matches!(err, DecodeError::CrcMismatch { expected: 7, .. }));
+        assert_eq!(out, b"data");
+    }
+
+    #[test]
+    fn test_read_entry_to_vec() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(dir.path(), &[("a.txt", b"alpha"), ("b/c.txt", b"gamma")]);
+        let extractor = ZipExtractor::new(&zip_path, dir.path().join("unused"));
+
+        let data = extractor.read_entry_to_vec("b/c.txt").unwrap();
+        assert_eq!(data, b"gamma");
+        assert!(data.capacity() >= 5 && data.capacity() < 64);
+        assert!(!dir.path().join("unused").exists());
+        assert!(matches!(
+            extractor.read_entry_to_vec("missing").unwrap_err(),
+            DecodeError::Zip(ZipError::FileNotFound)
+        ));
+
+        // 把中央目录中声明的解压后大小改大；伪造的巨大大小也只会得到错误，而不是过量分配
+        for declared in [7u32, 0xFFFF_FFF0] {
+            let mut bytes = data_descriptor_zip("x.txt", b"data", crc32fast::hash(b"data"));
+            let eocd = bytes.len() - 22;
+            let cd_offset =
+                u32::from_le_bytes(bytes[eocd + 16..eocd + 20].try_into().unwrap()) as usize;
+            bytes[cd_offset + 24..cd_offset + 28].copy_from_slice(&declared.to_le_bytes());
+            let bad = dir.path().join("bad.zip");
+            fs::write(&bad, bytes).unwrap();
+            let err = ZipExtractor::new(&bad, dir.path())
+                .read_entry_to_vec("x.txt")
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                DecodeError::EntrySizeMismatch { ref entry, declared: d, actual: 4 }
+                    if entry == "x.txt" && d == u64::from(declared)
+            ));
+        }
+    }
+
+    /// 大量小文件的耗时基准：`cargo test --release -- --ignored bench_many_small_files --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_many_small_files() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..20_000)
+            .map(|i| format!("d{}/f{}.txt", i % 50, i))
+            .collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|n| (n.as_str(), n.as_bytes())).collect();
+        let bytes = Arc::new(fs::read(write_zip(dir.path(), &entries)).unwrap());
+
+        for threads in [1, 4] {
+            let bytes = Arc::clone(&bytes);
+            let out = dir.path().join(format!("out{}", threads));
+            let start = Instant::now();
+            ZipExtractor::with_source(move || Ok(Cursor::new(bytes.as_ref().clone())), &out)
+                .worker_threads(threads)
+                .extract()
+                .unwrap();
+            println!(
+                "{} 个小文件, {} 线程: {:?}",
+                names.len(),
+                threads,
+                start.elapsed()
+            );
+        }
+
+        // 只解压到 sink，排除文件系统开销，主要体现拷贝缓冲区的分配成本
+        let start = Instant::now();
+        ZipExtractor::with_source(move || Ok(Cursor::new(bytes.as_ref().clone())), dir.path())
+            .test_archive()
+            .unwrap();
+        println!("{} 个小文件, 仅校验: {:?}", names.len(), start.elapsed());
+    }
+
+    #[test]
+    fn test_mmap_parallel() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..30).map(|i| format!("f{}.txt", i)).collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|n| (n.as_str(), n.as_bytes())).collect();
+        let zip_path = write_zip(dir.path(), &entries);
+
+        let out = dir.path().join("out");
+        ZipExtractor::new(&zip_path, &out)
+            .mmap(true)
+            .worker_threads(3)
+            .extract()
+            .unwrap();
+        for name in &names {
+            assert_eq!(fs::read_to_string(out.join(name)).unwrap(), *name);
+        }
+    }
+
+    /// 记录同时打开的句柄数的读取器
+    struct TrackedReader {
+        inner: Cursor<Vec<u8>>,
+        open: Arc<AtomicUsize>,
+    }
+
+    impl Read for TrackedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.inner.read(buf)
+        }
+    }
+
+    impl Seek for TrackedReader {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    impl Drop for TrackedReader {
+        fn drop(&mut self) {
+            self.open.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_parallel_stress_no_serialization() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..2_000)
+            .map(|i| format!("d{}/f{}.txt", i % 16, i))
+            .collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|n| (n.as_str(), n.as_bytes())).collect();
+        let bytes = Arc::new(fs::read(write_zip(dir.path(), &entries)).unwrap());
+
+        let open = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let source = {
+            let (open, peak) = (Arc::clone(&open), Arc::clone(&peak));
+            move || {
+                let now = open.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                // 放慢打开速度，让各工作线程的句柄生命周期相互重叠
+                std::thread::sleep(Duration::from_millis(20));
+                Ok(TrackedReader {
+                    inner: Cursor::new(bytes.as_ref().clone()),
+                    open: Arc::clone(&open),
+                })
+            }
+        };
+
+        let out = dir.path().join("out");
+        let (tx, rx) = std::sync::mpsc::channel();
+        let worker_out = out.clone();
+        std::thread::spawn(move || {
+            let report = ZipExtractor::with_source(source, &worker_out)
+                .worker_threads(16)
+                .extract();
+            tx.send(report).unwrap();
+        });
+
+        // 死锁时不会在限定时间内返回
+        let report = rx
+            .recv_timeout(Duration::from_secs(60))
+            .expect("extraction did not finish")
+            .unwrap();
+        assert_eq!(report.files_extracted, names.len());
+        assert_eq!(open.load(Ordering::SeqCst), 0);
+        // 除规划用的句柄外，至少有两个工作线程同时持有各自的句柄
+        assert!(peak.load(Ordering::SeqCst) >= 3);
+        for name in names.iter().step_by(97) {
+            assert_eq!(fs::read_to_string(out.join(name)).unwrap(), *name);
+        }
+    }
+
+    #[test]
+    fn test_custom_source_parallel() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..40)
+            .map(|i| format!("dir{}/file{}.txt", i % 4, i))
+            .collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|n| (n.as_str(), n.as_bytes())).collect();
+        let bytes = Arc::new(fs::read(write_zip(dir.path(), &entries)).unwrap());
+
+        let opened = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&opened);
+        let source = move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+            Ok(Cursor::new(bytes.as_ref().clone()))
+        };
+
+        let out = dir.path().join("out");
+        ZipExtractor::with_source(source, &out)
+            .worker_threads(4)
+            .extract()
+            .unwrap();
+
+        for name in &names {
+            assert_eq!(fs::read_to_string(out.join(name)).unwrap(), *name);
+        }
+        // 主句柄 + 每个工作线程各一个
+        assert_eq!(opened.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_worker_open_failure_fails_extraction() {
+        let dir = tempdir().unwrap();
+        let names: Vec<String> = (0..40).map(|i| format!("file{}.txt", i)).collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|n| (n.as_str(), n.as_bytes())).collect();
+        let bytes = Arc::new(fs::read(write_zip(dir.path(), &entries)).unwrap());
+
+        // 主句柄与第一个工作线程可以打开，之后的工作线程打开失败
+        let opened = Arc::new(AtomicU64::new(0));
+        let source = move || {
+            if opened.fetch_add(1, Ordering::Relaxed) >= 2 {
+                return Err(io::Error::other("source gone"));
+            }
+            Ok(Cursor::new(bytes.as_ref().clone()))
+        };
+
+        let err = ZipExtractor::with_source(source, dir.path().join("out"))
+            .worker_threads(4)
+            .extract()
+            .unwrap_err();
+        assert!(matches!(err, DecodeError::Io(ref e) if e.to_string() == "source gone"));
+    }
+
+    #[test]
+    fn test_on_conflict() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[
+                ("a.txt", b"new a"),
+                ("b.txt", b"new b"),
+                ("c.txt", b"new c"),
+                ("d.txt", b"new d"),
+            ],
+        );
+        let out = dir.path().join("out");
+        fs::create_dir_all(&out).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(out.join(name), "old").unwrap();
+        }
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&seen);
+        let report = ZipExtractor::new(&zip_path, &out)
+            .overwrite(OverwritePolicy::Error)
+            .on_conflict(move |path, info| {
+                recorded.lock().unwrap().push(info.name.clone());
+                assert!(path.exists());
+                match info.name.as_str() {
+                    "a.txt" => ConflictResolution::Overwrite,
+                    "b.txt" => ConflictResolution::Skip,
+                    _ => ConflictResolution::Rename(PathBuf::from("c (1).txt")),
+                }
+            })
+            .extract()
+            .unwrap();
+
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort();
+        assert_eq!(seen, ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(report.files_extracted, 3);
+        assert_eq!(
+            report.skipped,
+            vec![("b.txt".to_string(), SkipReason::AlreadyExists)]
+        );
+        assert_eq!(fs::read_to_string(out.join("a.txt")).unwrap(), "new a");
+        assert_eq!(fs::read_to_string(out.join("b.txt")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(out.join("c.txt")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(out.join("c (1).txt")).unwrap(), "new c");
+        assert_eq!(fs::read_to_string(out.join("d.txt")).unwrap(), "new d");
+
+        let err = ZipExtractor::new(&zip_path, &out)
+            .on_conflict(|_, _| ConflictResolution::Abort)
+            .worker_threads(1)
+            .extract()
+            .unwrap_err();
+        assert!(matches!(err, DecodeError::AlreadyExists(p) if p == out.join("a.txt")));
+
+        let err = ZipExtractor::new(&zip_path, &out)
+            .on_conflict(|_, _| ConflictResolution::Rename(PathBuf::from("../escape.txt")))
+            .worker_threads(1)
+            .extract()
+            .unwrap_err();
+        assert!(matches!(err, DecodeError::UnsafePath(_)));
+        assert!(!dir.path().join("escape.txt").exists());
+    }
+
+    #[test]
+    fn test_try_entries() {
+        let dir = tempdir().unwrap();
+        let zip_path = write_zip(
+            dir.path(),
+            &[("a.txt", b"a"), ("b.txt", b"b"), ("c.txt", b"c")],
+        );
+        let out = dir.path().join("out");
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("b.txt"), "old").unwrap();
+
+        let extractor = ZipExtractor::new(&zip_path, &out).overwrite(OverwritePolicy::Error);
+        let mut entries = extractor.try_entries();
+        assert_eq!(entries.next().unwrap().unwrap().name, "a.txt");
+        let err = entries.next().unwrap().unwrap_err();
+        assert!(matches!(err, DecodeError::AlreadyExists(p) if p == out.join("b.txt")));
+        // 失败后继续处理其余条目
+        assert_eq!(entries.next().unwrap().unwrap().name, "c.txt");
+        assert!(entries.next().is_none());
+        let report = entries.into_report();
+        assert_eq!(report.files_extracted, 2);
+        assert_eq!(fs::read_to_string(out.join("b.txt")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(out.join("c.txt")).unwrap(), "c");
+
+        // 打开失败时只产出一个错误
+        let missing = ZipExtractor::new(dir.path().join("missing.zip"), &out);
+        let results: Vec<_> = missing.try_entries().collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
     }
 }
@@ -1,78 +1,573 @@
-use anyhow::{Context, Result};
+use crate::FileOpError;
+use crate::decode::{FileKind, classify, detect_encoding};
+use rayon::prelude::*;
 use std::collections::HashMap;
 use std::fs;
-use std::{fs::File, path::Path};
+use std::io;
+use std::path::{Path, PathBuf};
 
+pub mod decoded;
+pub mod digest;
+pub mod entry;
+pub mod eocd;
 pub mod extractor;
+pub mod report;
+pub mod segments;
+pub mod source;
+pub mod transaction;
+
+pub use decoded::DecodedFile;
+pub use transaction::RenameTransaction;
+
+type Result<T> = std::result::Result<T, FileOpError>;
+
+/// 把访问 `path` 时的 IO 错误转换为 [`FileOpError::Io`]
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> FileOpError + '_ {
+    move |source| FileOpError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// 规范化后的扩展名重命名规则
+///
+/// 键统一转为小写并去掉前导点，因此 `".TXT"`、`"txt"` 会命中同一条规则。
+/// 特殊键 `"*"`（[`WILDCARD_EXTENSION`]）匹配所有没有显式规则的扩展名；
+/// 没有扩展名的文件不受它影响。目标为空字符串（[`STRIP_EXTENSION`]，`"."` 等价）时去掉扩展名：
+/// `file.bak` → `file`，`a.tar.gz` 只去掉最后一段变为 `a.tar`。
+#[derive(Debug, Clone, Default)]
+pub struct RenameRules {
+    map: HashMap<String, String>,
+}
+
+impl RenameRules {
+    /// 从调用方传入的映射构建规则
+    ///
+    /// 规范化后发生冲突且目标不同的键会输出警告，按原始键的字典序保留第一条。
+    pub fn from_map(rename_hash: &HashMap<String, String>) -> Self {
+        let mut entries: Vec<_> = rename_hash.iter().collect();
+        entries.sort();
+
+        let mut map: HashMap<String, String> = HashMap::with_capacity(entries.len());
+        for (key, target) in entries {
+            let key_norm = normalize_extension(key);
+            let target = target.trim_start_matches('.');
+            match map.get(&key_norm) {
+                Some(existing) if existing != target => {
+                    log::warn!(
+                        "扩展名规则冲突: {:?} 规范化为 {:?}, 已映射到 {:?}, 忽略 {:?}",
+                        key,
+                        key_norm,
+                        existing,
+                        target
+                    );
+                }
+                Some(_) => {}
+                None => {
+                    map.insert(key_norm, target.to_string());
+                }
+            }
+        }
+        Self { map }
+    }
+
+    /// 查找扩展名对应的新扩展名（大小写不敏感），显式规则优先于通配规则
+    pub fn get(&self, extension: &str) -> Option<&str> {
+        self.map
+            .get(&normalize_extension(extension))
+            .or_else(|| self.map.get(WILDCARD_EXTENSION))
+            .map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// 匹配任意未显式映射扩展名的规则键
+pub const WILDCARD_EXTENSION: &str = "*";
+
+/// 表示“去掉扩展名”的规则目标
+pub const STRIP_EXTENSION: &str = "";
+
+#[inline]
+fn normalize_extension(ext: &str) -> String {
+    ext.trim_start_matches('.').to_lowercase()
+}
 
 pub fn get_file_handles<P: AsRef<Path>>(
     path: P,
     rename_hash: &HashMap<String, String>,
-) -> Result<Vec<File>> {
-    let path = path.as_ref();
-    let metadata =
-        fs::metadata(path).with_context(|| format!("无法获取路径 {} 的元数据", path.display()))?;
+) -> Result<Vec<DecodedFile>> {
+    let rules = RenameRules::from_map(rename_hash);
+    handles_for(path.as_ref(), |file| rename_with_rules(file, &rules))
+}
+
+/// 与 [`get_file_handles`] 相同，但由闭包决定每个文件的新路径
+///
+/// `rename` 接收原路径，返回新路径，或返回 `None` 表示保持不变（返回原路径同样不会重命名）。
+/// 扩展名映射只是其中一种策略；与映射不同，没有扩展名的文件不会报错。
+/// 新路径所在目录不存在时返回错误，需要自动创建时使用 [`get_file_handles_with_options`]。
+pub fn get_file_handles_with<P, F>(path: P, rename: F) -> Result<Vec<DecodedFile>>
+where
+    P: AsRef<Path>,
+    F: Fn(&Path) -> Option<PathBuf>,
+{
+    get_file_handles_with_options(path, rename, &RenameOptions::default())
+}
+
+/// 与 [`get_file_handles_with`] 相同，按 `options` 处理目标目录
+pub fn get_file_handles_with_options<P, F>(
+    path: P,
+    rename: F,
+    options: &RenameOptions,
+) -> Result<Vec<DecodedFile>>
+where
+    P: AsRef<Path>,
+    F: Fn(&Path) -> Option<PathBuf>,
+{
+    handles_for(path.as_ref(), |file| {
+        rename_with_options(file, &rename, options)
+    })
+}
+
+/// 按闭包重命名时的选项
+#[derive(Debug, Clone, Default)]
+pub struct RenameOptions {
+    create_target_dirs: bool,
+}
+
+impl RenameOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 新路径所在目录不存在时是否自动创建（默认不创建，返回指明该目录的错误）
+    pub fn create_target_dirs(mut self, enabled: bool) -> Self {
+        self.create_target_dirs = enabled;
+        self
+    }
+}
+
+/// 与 [`get_file_handles`] 相同，但同时说明每个文件是否真的被重命名，便于记录审计日志或撤销
+pub fn get_file_handles_detailed<P: AsRef<Path>>(
+    path: P,
+    rename_hash: &HashMap<String, String>,
+) -> Result<Vec<HandledFile>> {
+    let rules = RenameRules::from_map(rename_hash);
+    handles_for(path.as_ref(), |file| {
+        check_extension(file, &rules)?;
+        rename_detailed(
+            file,
+            &|path: &Path| rule_target(path, &rules),
+            &RenameOptions::default(),
+        )
+    })
+}
+
+/// 对单个文件或目录中的文件（不递归）逐个调用 `handle`，遇到第一个错误即返回
+fn handles_for<T, F>(path: &Path, mut handle: F) -> Result<Vec<T>>
+where
+    F: FnMut(&Path) -> Result<T>,
+{
+    let metadata = fs::metadata(path).map_err(io_error(path))?;
 
     if metadata.is_file() {
         // 单个文件：直接处理并包装成 Vec
-        rename_file(path, rename_hash).map(|file| vec![file])
+        handle(path).map(|file| vec![file])
     } else if metadata.is_dir() {
         // 处理目录：收集所有文件结果
-        let entries =
-            fs::read_dir(path).with_context(|| format!("无法读取目录 {}", path.display()))?;
+        let entries = fs::read_dir(path).map_err(io_error(path))?;
 
         let mut files = Vec::new();
         for entry in entries {
-            let entry = entry?;
+            let entry = entry.map_err(io_error(path))?;
             let path = entry.path();
             if path.is_file() {
                 // 处理文件并收集结果
-                files.push(rename_file(&path, rename_hash)?);
+                files.push(handle(&path)?);
             }
         }
         Ok(files)
     } else {
-        anyhow::bail!("路径 {} 不是文件也不是目录", path.display());
+        Err(FileOpError::NotFileOrDir(path.to_path_buf()))
     }
 }
+
+/// 打开文件前对它执行的操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandleAction {
+    /// 从原路径重命名而来
+    Renamed(PathBuf),
+    /// 没有匹配的规则，直接打开原文件
+    Unchanged,
+}
+
+/// [`get_file_handles_detailed`] 返回的单个文件
+#[derive(Debug)]
+pub struct HandledFile {
+    /// 最终路径（重命名后为新路径）
+    pub path: PathBuf,
+    pub handle: DecodedFile,
+    pub action: HandleAction,
+}
+
+/// 多路径处理中单个文件的结果
+pub type FileHandleResult = (PathBuf, Result<DecodedFile>);
+
+/// 对任意一组路径（可来自不同目录）应用重命名规则，逐个收集结果
+///
+/// 规则只构建一次。目录按 [`get_file_handles`] 的方式展开其中的文件（不递归）；
+/// 单个文件失败不会中断其余文件，错误记录在对应的结果中；不存在的路径同样只产生一条错误结果。
+/// 结果按输入顺序排列。
+pub fn get_file_handles_multi(
+    paths: &[PathBuf],
+    rename_hash: &HashMap<String, String>,
+) -> Vec<FileHandleResult> {
+    let rules = RenameRules::from_map(rename_hash);
+    let mut results = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        if let Err(e) = fs::symlink_metadata(path) {
+            results.push((path.clone(), Err(io_error(path)(e))));
+        } else if path.is_dir() {
+            match fs::read_dir(path) {
+                Ok(entries) => {
+                    for entry in entries {
+                        match entry {
+                            Ok(entry) if entry.path().is_file() => {
+                                let file = entry.path();
+                                let result = rename_with_rules(&file, &rules);
+                                results.push((file, result));
+                            }
+                            Ok(_) => {}
+                            Err(e) => results.push((path.clone(), Err(io_error(path)(e)))),
+                        }
+                    }
+                }
+                Err(e) => results.push((path.clone(), Err(io_error(path)(e)))),
+            }
+        } else {
+            results.push((path.clone(), rename_with_rules(path, &rules)));
+        }
+    }
+    results
+}
+
+/// 与 [`get_file_handles`] 相同，但不会整体失败：路径不存在或无法读取时返回一条错误结果，
+/// 目录中单个文件的错误也只记录在该文件的结果中
+///
+/// 适合批量处理用户给出的路径列表，一个坏路径不会让整批任务失败。
+pub fn get_file_handles_lenient<P: AsRef<Path>>(
+    path: P,
+    rename_hash: &HashMap<String, String>,
+) -> Vec<FileHandleResult> {
+    get_file_handles_multi(&[path.as_ref().to_path_buf()], rename_hash)
+}
+
+/// 并行扫描并处理目录中的文件（不递归），适合网络存储上的大目录
+///
+/// 目录项的类型判断、重命名与打开都在最多 `concurrency` 个线程中进行（0 表示按 CPU 核数）。
+/// 单个文件失败不会中断其余文件；结果按路径排序，与线程调度无关。
+/// 多个文件被规则映射到同一目标名时结果取决于执行顺序，这种情况应使用 [`get_file_handles`]。
+pub fn get_file_handles_parallel<P: AsRef<Path>>(
+    path: P,
+    rename_hash: &HashMap<String, String>,
+    concurrency: usize,
+) -> Result<Vec<FileHandleResult>> {
+    let path = path.as_ref();
+    let rules = RenameRules::from_map(rename_hash);
+    let entries: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(io_error(path))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()
+        .map_err(io_error(path))?;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(concurrency)
+        .build()
+        .map_err(|e| io_error(path)(io::Error::other(e)))?;
+    let mut results: Vec<FileHandleResult> = pool.install(|| {
+        entries
+            .into_par_iter()
+            .filter(|path| path.is_file())
+            .map(|path| {
+                let result = rename_with_rules(&path, &rules);
+                (path, result)
+            })
+            .collect()
+    });
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(results)
+}
+
 #[inline]
-pub fn rename_file<P: AsRef<Path>>(path: P, rename_hash: &HashMap<String, String>) -> Result<File> {
-    let original_path = path.as_ref();
+pub fn rename_file<P: AsRef<Path>>(
+    path: P,
+    rename_hash: &HashMap<String, String>,
+) -> Result<DecodedFile> {
+    rename_with_rules(path, &RenameRules::from_map(rename_hash))
+}
+
+/// 对单个文件计划执行的操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameAction {
+    /// 重命名为 `to`；`overwrites` 表示目标已存在，执行时会被覆盖
+    Rename { to: PathBuf, overwrites: bool },
+    /// 没有匹配的规则（或规则映射到相同扩展名），保持不变
+    Keep,
+    /// 文件没有扩展名，执行时会报错
+    NoExtension,
+}
+
+/// [`plan_renames`] 返回的单条计划
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenamePlan {
+    pub path: PathBuf,
+    pub action: RenameAction,
+}
+
+/// 只计算重命名计划而不修改或打开任何文件，便于在批量执行前审查
+///
+/// `path` 为文件时返回一条计划；为目录时列出其中的文件，`recursive` 为真时包含子目录。
+/// 结果按路径排序。
+pub fn plan_renames<P: AsRef<Path>>(
+    path: P,
+    rename_hash: &HashMap<String, String>,
+    recursive: bool,
+) -> Result<Vec<RenamePlan>> {
+    let path = path.as_ref();
+    let rules = RenameRules::from_map(rename_hash);
+    let metadata = fs::metadata(path).map_err(io_error(path))?;
+
+    let mut files = Vec::new();
+    if metadata.is_dir() {
+        collect_files(path, recursive, &mut files)?;
+        files.sort();
+    } else {
+        files.push(path.to_path_buf());
+    }
+
+    Ok(files
+        .into_iter()
+        .map(|path| RenamePlan {
+            action: plan_action(&path, &rules),
+            path,
+        })
+        .collect())
+}
+
+fn collect_files(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir).map_err(io_error(dir))?;
+    for entry in entries {
+        let path = entry.map_err(io_error(dir))?.path();
+        if path.is_file() {
+            files.push(path);
+        } else if recursive && path.is_dir() {
+            collect_files(&path, recursive, files)?;
+        }
+    }
+    Ok(())
+}
 
-    // 获取扩展名（无扩展名时直接打开原文件）
-    let Some(extension) = original_path.extension().and_then(|ext| ext.to_str()) else {
-        anyhow::bail!("文件 {} 没有扩展名", original_path.display())
+fn plan_action(path: &Path, rules: &RenameRules) -> RenameAction {
+    let Some(extension) = path.extension().and_then(|ext| ext.to_str()) else {
+        return RenameAction::NoExtension;
     };
+    // 直接获取新文件名（避免 contains_key + get 的双重查找）；映射到相同扩展名时不重命名
+    match rules.get(extension).map(|name| path.with_extension(name)) {
+        Some(to) if to != path => RenameAction::Rename {
+            overwrites: to.exists(),
+            to,
+        },
+        _ => RenameAction::Keep,
+    }
+}
+
+/// 按已规范化的规则重命名单个文件并打开
+pub fn rename_with_rules<P: AsRef<Path>>(path: P, rules: &RenameRules) -> Result<DecodedFile> {
+    let original_path = path.as_ref();
+    check_extension(original_path, rules)?;
+    rename_with(original_path, &|path: &Path| rule_target(path, rules))
+}
+
+/// 按扩展名规则处理时，没有扩展名的文件视为错误
+fn check_extension(path: &Path, rules: &RenameRules) -> Result<()> {
+    if let RenameAction::NoExtension = plan_action(path, rules) {
+        return Err(FileOpError::NoExtension(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+/// 把扩展名规则转换为 [`rename_with`] 使用的闭包形式
+fn rule_target(path: &Path, rules: &RenameRules) -> Option<PathBuf> {
+    match plan_action(path, rules) {
+        RenameAction::Rename { to, .. } => Some(to),
+        _ => None,
+    }
+}
+
+/// 按闭包给出的新路径重命名单个文件并打开；闭包返回 `None` 或原路径时直接打开原文件
+pub fn rename_with<P, F>(path: P, rename: &F) -> Result<DecodedFile>
+where
+    P: AsRef<Path>,
+    F: Fn(&Path) -> Option<PathBuf>,
+{
+    rename_with_options(path, rename, &RenameOptions::default())
+}
+
+/// 与 [`rename_with`] 相同，按 `options` 处理目标目录
+pub fn rename_with_options<P, F>(
+    path: P,
+    rename: &F,
+    options: &RenameOptions,
+) -> Result<DecodedFile>
+where
+    P: AsRef<Path>,
+    F: Fn(&Path) -> Option<PathBuf>,
+{
+    rename_detailed(path.as_ref(), rename, options).map(|file| file.handle)
+}
 
-    // 直接获取新文件名（避免 contains_key + get 的双重查找）
-    match rename_hash.get(extension) {
-        Some(name) => {
-            let new_path = original_path.with_extension(name);
+/// 执行重命名并打开文件；目标目录不存在时按 `options` 创建或返回指明该目录的错误
+fn rename_detailed<F>(
+    original_path: &Path,
+    rename: &F,
+    options: &RenameOptions,
+) -> Result<HandledFile>
+where
+    F: Fn(&Path) -> Option<PathBuf>,
+{
+    match rename(original_path).filter(|to| to != original_path) {
+        Some(new_path) => {
+            if let Some(parent) = new_path.parent()
+                && !parent.as_os_str().is_empty()
+                && !parent.is_dir()
+            {
+                if !options.create_target_dirs {
+                    return Err(FileOpError::RenameFailed {
+                        from: original_path.to_path_buf(),
+                        to: new_path.clone(),
+                        source: io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("目标目录 {} 不存在", parent.display()),
+                        ),
+                    });
+                }
+                fs::create_dir_all(parent).map_err(io_error(parent))?;
+            }
             // 执行重命名操作
-            fs::rename(original_path, &new_path).with_context(|| {
-                format!(
-                    "Failed to rename {} to {}",
-                    original_path.display(),
-                    new_path.display()
-                )
+            fs::rename(original_path, &new_path).map_err(|source| FileOpError::RenameFailed {
+                from: original_path.to_path_buf(),
+                to: new_path.clone(),
+                source,
             })?;
-            File::open(&new_path)
-                .with_context(|| format!("无法打开重命名后的文件 {}", new_path.display()))
+            let handle =
+                DecodedFile::open(&new_path).map_err(|source| FileOpError::OpenFailed {
+                    path: new_path.clone(),
+                    source,
+                })?;
+            Ok(HandledFile {
+                path: new_path,
+                handle,
+                action: HandleAction::Renamed(original_path.to_path_buf()),
+            })
         }
         None => {
-            // 如果没有对应的重命名规则，直接打开原文件
-            File::open(original_path)
-                .with_context(|| format!("无法打开原文件 {}", original_path.display()))
+            // 没有需要执行的重命名，直接打开原文件
+            let handle =
+                DecodedFile::open(original_path).map_err(|source| FileOpError::OpenFailed {
+                    path: original_path.to_path_buf(),
+                    source,
+                })?;
+            Ok(HandledFile {
+                path: original_path.to_path_buf(),
+                handle,
+                action: HandleAction::Unchanged,
+            })
         }
     }
 }
+
+/// 按检测出的编码整理文件的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodingLayout {
+    /// 在扩展名前插入编码名：`notes.txt` → `notes.gbk.txt`
+    Suffix,
+    /// 移动到同级的编码子目录：`notes.txt` → `gbk/notes.txt`
+    Subfolder,
+}
+
+/// 检测文件编码，并按 `layout` 重命名或移动，返回新路径
+///
+/// 编码名取小写形式（如 `gbk`、`utf-16le`）。文件名中已带有相同编码后缀、
+/// 或已位于同名子目录中时不做改动；[`classify`] 判为二进制的文件同样不做改动。
+pub fn rename_by_encoding<P: AsRef<Path>>(path: P, layout: EncodingLayout) -> Result<PathBuf> {
+    let original_path = path.as_ref();
+    if classify(original_path).map_err(io_error(original_path))? == FileKind::Binary {
+        return Ok(original_path.to_path_buf());
+    }
+    let bytes = fs::read(original_path).map_err(io_error(original_path))?;
+    let charset = detect_encoding(&bytes).encoding.name().to_lowercase();
+
+    let Some(file_name) = original_path.file_name().and_then(|name| name.to_str()) else {
+        return Err(io_error(original_path)(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "文件名不是有效的 UTF-8",
+        )));
+    };
+    let parent = original_path.parent().unwrap_or(Path::new(""));
+
+    let new_path = match layout {
+        EncodingLayout::Suffix => {
+            let stem = original_path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or(file_name);
+            if Path::new(stem)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(&charset))
+            {
+                return Ok(original_path.to_path_buf());
+            }
+            let new_name = match original_path.extension().and_then(|ext| ext.to_str()) {
+                Some(ext) => format!("{stem}.{charset}.{ext}"),
+                None => format!("{file_name}.{charset}"),
+            };
+            parent.join(new_name)
+        }
+        EncodingLayout::Subfolder => {
+            if parent
+                .file_name()
+                .is_some_and(|dir| dir.eq_ignore_ascii_case(&charset))
+            {
+                return Ok(original_path.to_path_buf());
+            }
+            let folder = parent.join(&charset);
+            fs::create_dir_all(&folder).map_err(io_error(&folder))?;
+            folder.join(file_name)
+        }
+    };
+
+    fs::rename(original_path, &new_path).map_err(|source| FileOpError::RenameFailed {
+        from: original_path.to_path_buf(),
+        to: new_path.clone(),
+        source,
+    })?;
+    Ok(new_path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::collections::HashMap;
     use std::fs::{self, File};
     use std::io::Write;
-    use tempfile::{NamedTempFile, tempdir};
+    use tempfile::tempdir;
 
     #[test]
     fn test_rename_with_extension_in_map() -> anyhow::Result<()> {
@@ -85,7 +580,8 @@ mod tests {
         rename_map.insert("txt".to_string(), "md".to_string());
 
         let result = rename_file(&file_path, &rename_map)?;
-        assert!(result.metadata().is_ok());
+        assert!(result.file().metadata().is_ok());
+        assert_eq!(result.path(), dir.path().join("test.md"));
 
         // 验证原文件已不存在
         assert!(!file_path.exists());
@@ -98,6 +594,40 @@ mod tests {
 
         Ok(())
     }
+    #[test]
+    fn test_rename_rules_normalize_keys() -> anyhow::Result<()> {
+        let mut rename_map = HashMap::new();
+        rename_map.insert(".TXT".to_string(), ".md".to_string());
+        rename_map.insert("Log".to_string(), "txt".to_string());
+
+        let rules = RenameRules::from_map(&rename_map);
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules.get("txt"), Some("md"));
+        assert_eq!(rules.get("TxT"), Some("md"));
+        assert_eq!(rules.get("log"), Some("txt"));
+
+        // 大写扩展名的文件也应命中规则
+        let dir = tempdir()?;
+        let file_path = dir.path().join("upper.TXT");
+        File::create(&file_path)?;
+        rename_file(&file_path, &rename_map)?;
+        assert!(dir.path().join("upper.md").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_rules_collision_keeps_first() {
+        let mut rename_map = HashMap::new();
+        rename_map.insert("txt".to_string(), "md".to_string());
+        rename_map.insert(".txt".to_string(), "rst".to_string());
+        rename_map.insert("TXT".to_string(), "md".to_string());
+
+        // 按原始键字典序 ".txt" 最先出现
+        let rules = RenameRules::from_map(&rename_map);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules.get("txt"), Some("rst"));
+    }
+
     #[test]
     fn test_directory_path() {
         // 创建临时目录
@@ -121,4 +651,356 @@ mod tests {
         let files = result.unwrap();
         assert_eq!(files.len(), 3);
     }
+
+    #[test]
+    fn test_rename_by_encoding() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let (gbk, _, _) =
+            encoding_rs::GBK.encode("这是一段用国标编码保存的中文字幕文件内容，用来测试编码检测。");
+        let notes = dir.path().join("notes.txt");
+        fs::write(&notes, &gbk)?;
+
+        let renamed = rename_by_encoding(&notes, EncodingLayout::Suffix)?;
+        assert_eq!(renamed, dir.path().join("notes.gbk.txt"));
+        assert!(!notes.exists());
+        // 已带编码后缀时保持不变
+        assert_eq!(
+            rename_by_encoding(&renamed, EncodingLayout::Suffix)?,
+            renamed
+        );
+
+        let readme = dir.path().join("README");
+        fs::write(&readme, "纯 UTF-8 内容")?;
+        let moved = rename_by_encoding(&readme, EncodingLayout::Subfolder)?;
+        assert_eq!(moved, dir.path().join("utf-8").join("README"));
+        assert_eq!(fs::read_to_string(&moved)?, "纯 UTF-8 内容");
+        assert_eq!(
+            rename_by_encoding(&moved, EncodingLayout::Subfolder)?,
+            moved
+        );
+
+        let image = dir.path().join("cover.png");
+        fs::write(&image, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR")?;
+        assert_eq!(
+            rename_by_encoding(&image, EncodingLayout::Subfolder)?,
+            image
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_file_handles_parallel() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        for i in 0..50 {
+            fs::write(dir.path().join(format!("f{:02}.txt", i)), "x")?;
+        }
+        fs::write(dir.path().join("noext"), "x")?;
+        fs::create_dir(dir.path().join("sub"))?;
+
+        let mut rename_map = HashMap::new();
+        rename_map.insert("txt".to_string(), "md".to_string());
+
+        let results = get_file_handles_parallel(dir.path(), &rename_map, 4)?;
+        assert_eq!(results.len(), 51);
+        // 结果按原路径排序，没有扩展名的文件只影响自己的结果
+        assert_eq!(results[0].0, dir.path().join("f00.txt"));
+        assert_eq!(
+            results[0].1.as_ref().unwrap().path(),
+            dir.path().join("f00.md")
+        );
+        assert!(results.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(results[50].0, dir.path().join("noext"));
+        assert!(results[50].1.is_err());
+        assert!(dir.path().join("f49.md").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_file_handles_multi() -> anyhow::Result<()> {
+        let dir_a = tempdir()?;
+        let dir_b = tempdir()?;
+        let a = dir_a.path().join("a.txt");
+        let b = dir_b.path().join("b.log");
+        fs::write(&a, "a")?;
+        fs::write(&b, "b")?;
+        fs::write(dir_b.path().join("c.txt"), "c")?;
+        let missing = dir_a.path().join("missing.txt");
+
+        let mut rename_map = HashMap::new();
+        rename_map.insert("txt".to_string(), "md".to_string());
+
+        let results = get_file_handles_multi(
+            &[
+                a.clone(),
+                missing.clone(),
+                b.clone(),
+                dir_b.path().join("c.txt"),
+            ],
+            &rename_map,
+        );
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].0, a);
+        assert_eq!(
+            results[0].1.as_ref().unwrap().path(),
+            dir_a.path().join("a.md")
+        );
+        // 缺失的文件只影响自己的结果
+        assert_eq!(results[1].0, missing);
+        assert!(results[1].1.is_err());
+        assert_eq!(results[2].1.as_ref().unwrap().path(), b);
+        assert!(dir_b.path().join("c.md").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_identity_mapping() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("same.txt");
+        fs::write(&file_path, "unchanged")?;
+        let modified = fs::metadata(&file_path)?.modified()?;
+
+        let mut rename_map = HashMap::new();
+        rename_map.insert("txt".to_string(), "txt".to_string());
+
+        let mut result = rename_file(&file_path, &rename_map)?;
+        assert_eq!(result.path(), file_path);
+        assert_eq!(result.read_to_string_decoded()?, "unchanged");
+        assert_eq!(fs::metadata(&file_path)?.modified()?, modified);
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_renames_is_dry_run() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        fs::create_dir(dir.path().join("sub"))?;
+        fs::write(dir.path().join("a.txt"), "a")?;
+        fs::write(dir.path().join("a.md"), "existing")?;
+        fs::write(dir.path().join("NOEXT"), "")?;
+        fs::write(dir.path().join("sub/b.txt"), "b")?;
+
+        let mut rename_map = HashMap::new();
+        rename_map.insert("txt".to_string(), "md".to_string());
+
+        let plans = plan_renames(dir.path(), &rename_map, true)?;
+        let summary: Vec<_> = plans
+            .iter()
+            .map(|p| {
+                (
+                    p.path.strip_prefix(dir.path()).unwrap().to_path_buf(),
+                    p.action.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (PathBuf::from("NOEXT"), RenameAction::NoExtension),
+                (PathBuf::from("a.md"), RenameAction::Keep),
+                (
+                    PathBuf::from("a.txt"),
+                    RenameAction::Rename {
+                        to: dir.path().join("a.md"),
+                        overwrites: true
+                    }
+                ),
+                (
+                    PathBuf::from("sub/b.txt"),
+                    RenameAction::Rename {
+                        to: dir.path().join("sub/b.md"),
+                        overwrites: false
+                    }
+                ),
+            ]
+        );
+        // 没有执行任何重命名
+        assert!(dir.path().join("a.txt").exists());
+        assert!(!dir.path().join("sub/b.md").exists());
+
+        assert_eq!(plan_renames(dir.path(), &rename_map, false)?.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_file_handles_with_closure() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("draft-notes.txt"), "a")?;
+        fs::write(dir.path().join("README"), "b")?;
+        fs::write(dir.path().join("keep.txt"), "c")?;
+
+        // 按整个文件名决定新名字，没有扩展名的文件也可以处理
+        let handles = get_file_handles_with(dir.path(), |path: &Path| {
+            let name = path.file_name()?.to_str()?;
+            if let Some(rest) = name.strip_prefix("draft-") {
+                Some(path.with_file_name(rest))
+            } else if name == "README" {
+                Some(path.with_file_name("README.md"))
+            } else {
+                None
+            }
+        })?;
+
+        let mut paths: Vec<_> = handles.iter().map(|f| f.path().to_path_buf()).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                dir.path().join("README.md"),
+                dir.path().join("keep.txt"),
+                dir.path().join("notes.txt"),
+            ]
+        );
+        assert!(!dir.path().join("draft-notes.txt").exists());
+        assert_eq!(fs::read_to_string(dir.path().join("README.md"))?, "b");
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_file_handles_detailed() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("a.txt"), "a")?;
+        fs::write(dir.path().join("b.md"), "b")?;
+
+        let mut rename_map = HashMap::new();
+        rename_map.insert("txt".to_string(), "md".to_string());
+        let mut files = get_file_handles_detailed(dir.path(), &rename_map)?;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, dir.path().join("a.md"));
+        assert_eq!(files[0].handle.path(), files[0].path);
+        assert_eq!(
+            files[0].action,
+            HandleAction::Renamed(dir.path().join("a.txt"))
+        );
+        assert_eq!(files[1].path, dir.path().join("b.md"));
+        assert_eq!(files[1].action, HandleAction::Unchanged);
+
+        fs::write(dir.path().join("noext"), "x")?;
+        assert!(get_file_handles_detailed(dir.path(), &rename_map).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_wildcard_extension() -> anyhow::Result<()> {
+        let mut rename_map = HashMap::new();
+        rename_map.insert("*".to_string(), "bin".to_string());
+        rename_map.insert("TXT".to_string(), "md".to_string());
+        let rules = RenameRules::from_map(&rename_map);
+        assert_eq!(rules.get("txt"), Some("md"));
+        assert_eq!(rules.get("csv"), Some("bin"));
+
+        let dir = tempdir()?;
+        for name in ["a.txt", "b.csv", "c.bin", "noext"] {
+            fs::write(dir.path().join(name), name)?;
+        }
+        let plans = plan_renames(dir.path(), &rename_map, false)?;
+        let actions: Vec<_> = plans.into_iter().map(|plan| plan.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                RenameAction::Rename {
+                    to: dir.path().join("a.md"),
+                    overwrites: false,
+                },
+                RenameAction::Rename {
+                    to: dir.path().join("b.bin"),
+                    overwrites: false,
+                },
+                // 通配规则映射到相同扩展名时保持不变
+                RenameAction::Keep,
+                // 通配规则不会给没有扩展名的文件加上扩展名
+                RenameAction::NoExtension,
+            ]
+        );
+        assert!(rename_file(dir.path().join("noext"), &rename_map).is_err());
+        assert_eq!(
+            rename_file(dir.path().join("b.csv"), &rename_map)?.path(),
+            dir.path().join("b.bin")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_target_dirs() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "a")?;
+        let target = dir.path().join("sorted").join("text").join("a.txt");
+        let rename = |_: &Path| Some(target.clone());
+
+        let err = rename_with(&file, &rename).unwrap_err();
+        assert!(matches!(
+            &err,
+            FileOpError::RenameFailed { from, to, source }
+                if from == &file && to == &target && source.kind() == io::ErrorKind::NotFound
+        ));
+        let err = err.to_string();
+        assert!(err.contains("目标目录"), "{}", err);
+        assert!(err.contains(&dir.path().join("sorted").join("text").display().to_string()));
+        assert!(file.exists());
+
+        let options = RenameOptions::new().create_target_dirs(true);
+        let handle = rename_with_options(&file, &rename, &options)?;
+        assert_eq!(handle.path(), target);
+        assert_eq!(fs::read_to_string(&target)?, "a");
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_extension() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        for name in ["file.bak", "a.tar.gz", "keep.txt", "README"] {
+            fs::write(dir.path().join(name), name)?;
+        }
+        let mut rename_map = HashMap::new();
+        rename_map.insert("bak".to_string(), STRIP_EXTENSION.to_string());
+        rename_map.insert("gz".to_string(), ".".to_string());
+
+        let stripped = rename_file(dir.path().join("file.bak"), &rename_map)?;
+        assert_eq!(stripped.path(), dir.path().join("file"));
+        assert_eq!(fs::read_to_string(dir.path().join("file"))?, "file.bak");
+        assert_eq!(
+            rename_file(dir.path().join("a.tar.gz"), &rename_map)?.path(),
+            dir.path().join("a.tar")
+        );
+        assert_eq!(
+            rename_file(dir.path().join("keep.txt"), &rename_map)?.path(),
+            dir.path().join("keep.txt")
+        );
+        // 没有扩展名的文件不会因去掉扩展名的规则被处理
+        assert!(rename_file(dir.path().join("README"), &rename_map).is_err());
+        // 去掉扩展名后的文件没有扩展名，再次处理同样报错而不是继续剥离
+        assert!(rename_file(dir.path().join("file"), &rename_map).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_file_handles_lenient() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("a.txt"), "a")?;
+        fs::write(dir.path().join("noext"), "x")?;
+        let mut rename_map = HashMap::new();
+        rename_map.insert("txt".to_string(), "md".to_string());
+
+        let missing = dir.path().join("missing");
+        assert!(get_file_handles(&missing, &rename_map).is_err());
+        let results = get_file_handles_lenient(&missing, &rename_map);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, missing);
+        assert!(matches!(
+            &results[0].1,
+            Err(FileOpError::Io { path, source })
+                if path == &missing && source.kind() == io::ErrorKind::NotFound
+        ));
+
+        let mut results = get_file_handles_lenient(dir.path(), &rename_map);
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].1.as_ref().unwrap().path(),
+            dir.path().join("a.md")
+        );
+        assert!(results[1].1.is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,158 @@
+use crate::DecodeError;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 条目被跳过的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// 与先出现的条目仅大小写或 Unicode 规范化不同（记录先出现的条目名）
+    CaseCollision(String),
+    /// 目标文件已存在且覆盖策略为跳过
+    AlreadyExists,
+    /// 设置了前缀路由但条目不匹配任何前缀，且未路由条目被设为跳过
+    Unrouted,
+    /// 检查点记录该条目已完成（开启 CRC 校验时已确认输出文件完好）
+    Resumed,
+    /// 自定义的条目名转换返回了 `None`
+    NameRejected,
+    /// macOS 生成的元数据（`__MACOSX/`、`.DS_Store`、`._*`）
+    MacMetadata,
+    /// 覆盖策略为 `IfNewer` 且条目不比已有文件新
+    NotNewer,
+    /// 扩展名不在允许写出的白名单中
+    DisallowedExtension,
+    /// 条目是绝对路径且策略为跳过
+    AbsolutePath,
+    /// 按内容检测出的 MIME 类型不在允许的范围内（或无法识别）
+    ContentType,
+    /// 覆盖策略为 `IfDifferent` 且已有文件与条目内容相同
+    Unchanged,
+    /// 符号链接条目，且未开启 `symlinks_as_files`
+    Symlink,
+    /// 解压后大小不在 `min_entry_size`..=`max_entry_size` 范围内
+    SizeOutOfRange,
+}
+
+/// 一次解压的结果统计
+#[derive(Debug, Clone, Default)]
+pub struct ExtractionReport {
+    /// 写出的文件数（包含空文件）
+    pub files_extracted: usize,
+    /// 其中长度为 0 的文件数
+    pub empty_files: usize,
+    /// 创建的目录条目数
+    pub dirs_created: usize,
+    /// 写出的解压后字节数
+    pub bytes_written: u64,
+    /// 已写出文件在归档中的压缩后字节数
+    pub compressed_bytes: u64,
+    /// 被跳过的条目及原因
+    pub skipped: Vec<(String, SkipReason)>,
+    /// 开启 `collect_crc_errors` 时 CRC 校验失败的条目
+    pub corrupt: Vec<Mismatch>,
+    /// 压缩比超过 `warn_compression_ratio` 的文件条目及其压缩比
+    pub suspicious_entries: Vec<(String, f64)>,
+    /// 作为绝对路径被去掉前缀后解压到输出目录内的条目名
+    pub relativized: Vec<String>,
+    /// 开启 `track_slowest` 时解压最慢的文件条目及其耗时，从慢到快排列
+    pub slowest_entries: Vec<(String, Duration)>,
+    /// 开启 `adaptive_buffers` 时最终选出的 `(读块, 写缓冲)` 大小；样本不足以做出选择时为 `None`
+    pub buffer_sizes: Option<(usize, usize)>,
+    /// 实际使用的解压线程数（顺序解压为 1），可用来确认 `worker_threads` 或自动选择的结果
+    pub threads_used: usize,
+    /// 总耗时
+    pub duration: Duration,
+    /// 从 URL 下载的归档字节数（不是从 URL 创建的解压器为 0）
+    pub downloaded_bytes: u64,
+    /// 已写出（或从检查点恢复）的文件条目：`(条目名, CRC32, 大小)`
+    pub(crate) entry_crcs: Vec<(String, u32, u64)>,
+}
+
+impl ExtractionReport {
+    /// 合并另一个（通常来自工作线程的）统计结果
+    pub(crate) fn merge(&mut self, other: ExtractionReport) {
+        self.files_extracted += other.files_extracted;
+        self.empty_files += other.empty_files;
+        self.dirs_created += other.dirs_created;
+        self.bytes_written += other.bytes_written;
+        self.compressed_bytes += other.compressed_bytes;
+        self.skipped.extend(other.skipped);
+        self.corrupt.extend(other.corrupt);
+        self.suspicious_entries.extend(other.suspicious_entries);
+        self.relativized.extend(other.relativized);
+        self.entry_crcs.extend(other.entry_crcs);
+        self.slowest_entries.extend(other.slowest_entries);
+    }
+
+    /// 只保留最慢的 `count` 个条目
+    pub(crate) fn keep_slowest(&mut self, count: usize) {
+        self.slowest_entries
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        self.slowest_entries.truncate(count);
+    }
+
+    /// 实际达到的压缩比（解压后字节数 / 压缩后字节数），没有压缩数据时为 `None`
+    ///
+    /// 接近 1 说明内容本身已经是压缩格式（图片、视频等），文本通常远大于 1。
+    pub fn compression_ratio(&self) -> Option<f64> {
+        (self.compressed_bytes > 0)
+            .then(|| self.bytes_written as f64 / self.compressed_bytes as f64)
+    }
+
+    /// 整个解压结果的内容摘要（64 位十六进制 SHA-256）
+    ///
+    /// 由按条目名排序后的每个文件的名称、CRC32 与大小计算，无需再次读取磁盘，
+    /// 与解压顺序和线程数无关。内容相同的两次解压得到相同的摘要，可用于比较或作为缓存键；
+    /// 由于基于 CRC32，它不能抵御刻意构造的碰撞。
+    pub fn content_digest(&self) -> String {
+        let mut entries: Vec<_> = self.entry_crcs.iter().collect();
+        entries.sort();
+
+        let mut hasher = Sha256::new();
+        for (name, crc, size) in entries {
+            hasher.update((name.len() as u64).to_le_bytes());
+            hasher.update(name.as_bytes());
+            hasher.update(crc.to_le_bytes());
+            hasher.update(size.to_le_bytes());
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// 中途失败的解压：导致中止的错误，以及此前已完成部分的统计
+///
+/// 自身的消息不重复 `error` 的内容，后者通过 [`source`](std::error::Error::source) 取得，
+/// 逐级打印错误链时不会出现两次。
+#[derive(Debug, thiserror::Error)]
+#[error("解压中途失败")]
+pub struct PartialExtraction {
+    #[source]
+    pub error: DecodeError,
+    pub report: ExtractionReport,
+}
+
+/// [`verify_extracted`](super::extractor::ZipExtractor::verify_extracted) 发现的差异
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// 归档中的条目名
+    pub entry: String,
+    /// 被检查的输出文件
+    pub path: PathBuf,
+    pub kind: MismatchKind,
+}
+
+/// 输出文件与归档条目不一致的方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MismatchKind {
+    /// 输出文件不存在
+    Missing,
+    /// 文件大小不同
+    SizeDiffers { expected: u64, actual: u64 },
+    /// 大小相同但内容的 CRC32 不同
+    CrcDiffers { expected: u32, actual: u32 },
+}
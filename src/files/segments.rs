@@ -0,0 +1,364 @@
+use super::eocd::{
+    self, CENTRAL_HEADER_LEN, CENTRAL_HEADER_SIGNATURE, EndOfCentralDirectory, read_u16, read_u32,
+};
+use super::source::{ArchiveSource, ReadSeek};
+use crate::DecodeError;
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::PathBuf,
+    sync::Arc,
+};
+
+/// 分卷归档第一个分卷开头的签名 `PK\x07\x08`
+const SPLIT_SIGNATURE: u32 = 0x08074b50;
+/// 只有一个分卷的“分卷”归档使用的临时签名 `PK00`
+const SINGLE_SPLIT_SIGNATURE: u32 = 0x30304b50;
+
+/// 由 `.z01`..`.zNN` 与最后的 `.zip` 分卷组成的归档
+///
+/// 分卷归档中的偏移都相对于各自的分卷，zip 库无法直接读取。这里把所有分卷按顺序
+/// 拼接成一个逻辑文件，并在末尾附加改写后的中央目录：条目偏移换算为拼接后的绝对偏移、
+/// 分卷号清零，zip 库看到的就是一个普通归档。分卷文件本身不会被修改。
+#[derive(Debug, Clone)]
+pub struct SegmentedSource {
+    parts: Arc<Vec<Part>>,
+    len: u64,
+}
+
+/// 逻辑文件中的一段
+#[derive(Debug)]
+struct Part {
+    start: u64,
+    len: u64,
+    data: PartData,
+}
+
+#[derive(Debug)]
+enum PartData {
+    File(PathBuf),
+    /// 改写后的中央目录与结束记录
+    Memory(Vec<u8>),
+}
+
+impl SegmentedSource {
+    /// 按顺序打开全部分卷（最后一个是包含中央目录结束记录的 `.zip`）
+    ///
+    /// 会校验分卷数与结束记录中的分卷号一致、每个条目指向的位置确实是本地文件头，
+    /// 分卷缺失、顺序错误或大小不符时返回 [`DecodeError::InvalidSegments`]。
+    /// 暂不支持 ZIP64 分卷归档。
+    pub fn open(paths: &[PathBuf]) -> Result<Self, DecodeError> {
+        let Some(last) = paths.last() else {
+            return Err(invalid("没有提供任何分卷"));
+        };
+
+        let mut parts = Vec::with_capacity(paths.len() + 1);
+        let mut len = 0;
+        for path in paths {
+            let part_len = std::fs::metadata(path)?.len();
+            parts.push(Part {
+                start: len,
+                len: part_len,
+                data: PartData::File(path.clone()),
+            });
+            len += part_len;
+        }
+        let mut source = Self {
+            parts: Arc::new(parts),
+            len,
+        };
+        let mut reader = source.reader();
+
+        let signature = read_u32_at(&mut reader, 0)?;
+        if paths.len() > 1 && signature != SPLIT_SIGNATURE && signature != SINGLE_SPLIT_SIGNATURE {
+            return Err(invalid("第一个分卷没有分卷签名，分卷顺序可能有误"));
+        }
+
+        let eocd = EndOfCentralDirectory::find(&mut BufReader::new(File::open(last)?))?
+            .ok_or_else(|| invalid("最后一个分卷中找不到中央目录结束记录"))?;
+        if eocd.disk_number as usize + 1 != paths.len() {
+            return Err(invalid(&format!(
+                "归档共有 {} 个分卷，提供了 {} 个",
+                eocd.disk_number as u32 + 1,
+                paths.len()
+            )));
+        }
+        if eocd.entries == u16::MAX
+            || eocd.central_directory_size == u32::MAX
+            || eocd.central_directory_offset == u32::MAX
+        {
+            return Err(invalid("暂不支持 ZIP64 分卷归档"));
+        }
+
+        let disk_start = |disk: u16| -> Result<u64, DecodeError> {
+            source
+                .parts
+                .get(disk as usize)
+                .map(|part| part.start)
+                .ok_or_else(|| invalid(&format!("条目位于不存在的分卷 {}", disk)))
+        };
+        let central_start =
+            disk_start(eocd.central_directory_disk)? + eocd.central_directory_offset as u64;
+        let central_end = central_start + eocd.central_directory_size as u64;
+        if central_end > len {
+            return Err(invalid("中央目录超出分卷总大小，分卷可能不完整"));
+        }
+
+        let mut central = vec![0u8; eocd.central_directory_size as usize];
+        reader.seek(SeekFrom::Start(central_start))?;
+        reader.read_exact(&mut central)?;
+
+        // 把每个条目的（分卷号, 分卷内偏移）改写为拼接后的绝对偏移
+        let mut at = 0;
+        for _ in 0..eocd.entries {
+            if at + CENTRAL_HEADER_LEN > central.len()
+                || read_u32(&central, at) != CENTRAL_HEADER_SIGNATURE
+            {
+                return Err(invalid("中央目录已损坏"));
+            }
+            let disk = read_u16(&central, at + 34);
+            let offset = read_u32(&central, at + 42);
+            if offset == u32::MAX {
+                return Err(invalid("暂不支持 ZIP64 分卷归档"));
+            }
+            let absolute = disk_start(disk)? + offset as u64;
+            if absolute >= len
+                || read_u32_at(&mut reader, absolute)? != eocd::LOCAL_HEADER_SIGNATURE
+            {
+                return Err(invalid(&format!(
+                    "分卷 {} 偏移 {} 处不是本地文件头，分卷顺序或大小有误",
+                    disk + 1,
+                    offset
+                )));
+            }
+            let absolute =
+                u32::try_from(absolute).map_err(|_| invalid("暂不支持 ZIP64 分卷归档"))?;
+            central[at + 34..at + 36].copy_from_slice(&0u16.to_le_bytes());
+            central[at + 42..at + 46].copy_from_slice(&absolute.to_le_bytes());
+
+            at += CENTRAL_HEADER_LEN
+                + read_u16(&central, at + 28) as usize
+                + read_u16(&central, at + 30) as usize
+                + read_u16(&central, at + 32) as usize;
+        }
+
+        // 附加的中央目录紧跟在所有分卷之后
+        let central_offset = u32::try_from(len).map_err(|_| invalid("暂不支持 ZIP64 分卷归档"))?;
+        let mut tail = central;
+        tail.extend_from_slice(&eocd::EOCD_SIGNATURE.to_le_bytes());
+        tail.extend_from_slice(&0u16.to_le_bytes()); // 本分卷编号
+        tail.extend_from_slice(&0u16.to_le_bytes()); // 中央目录所在分卷
+        tail.extend_from_slice(&eocd.entries.to_le_bytes());
+        tail.extend_from_slice(&eocd.entries.to_le_bytes());
+        tail.extend_from_slice(&eocd.central_directory_size.to_le_bytes());
+        tail.extend_from_slice(&central_offset.to_le_bytes());
+        tail.extend_from_slice(&(eocd.comment.len() as u16).to_le_bytes());
+        tail.extend_from_slice(&eocd.comment);
+
+        drop(reader);
+        let mut parts = Arc::try_unwrap(source.parts).expect("校验用的读取器已释放");
+        parts.push(Part {
+            start: len,
+            len: tail.len() as u64,
+            data: PartData::Memory(tail),
+        });
+        source.len = len + parts.last().map_or(0, |part| part.len);
+        source.parts = Arc::new(parts);
+        Ok(source)
+    }
+
+    /// 逻辑文件的总长度（包含附加的中央目录）
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn reader(&self) -> SegmentReader {
+        SegmentReader {
+            files: (0..self.parts.len()).map(|_| None).collect(),
+            parts: Arc::clone(&self.parts),
+            len: self.len,
+            pos: 0,
+        }
+    }
+}
+
+impl ArchiveSource for SegmentedSource {
+    fn open(&self) -> io::Result<Box<dyn ReadSeek>> {
+        Ok(Box::new(BufReader::with_capacity(64 * 1024, self.reader())))
+    }
+}
+
+/// 在拼接后的逻辑文件上读取，分卷文件在首次读到时才打开
+struct SegmentReader {
+    parts: Arc<Vec<Part>>,
+    files: Vec<Option<File>>,
+    len: u64,
+    pos: u64,
+}
+
+impl Read for SegmentReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        // 最后一个起点不超过当前位置的分段（跳过长度为 0 的分卷）
+        let index = self.parts.partition_point(|part| part.start <= self.pos) - 1;
+        let part = &self.parts[index];
+        let within = self.pos - part.start;
+        let max = buf
+            .len()
+            .min(usize::try_from(part.len - within).unwrap_or(usize::MAX));
+
+        let n = match &part.data {
+            PartData::Memory(bytes) => {
+                let within = within as usize;
+                buf[..max].copy_from_slice(&bytes[within..within + max]);
+                max
+            }
+            PartData::File(path) => {
+                let file = match &mut self.files[index] {
+                    Some(file) => file,
+                    slot => slot.insert(File::open(path)?),
+                };
+                file.seek(SeekFrom::Start(within))?;
+                file.read(&mut buf[..max])?
+            }
+        };
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for SegmentReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+        self.pos = target
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "定位到了数据源开头之前"))?;
+        Ok(self.pos)
+    }
+}
+
+fn invalid(reason: &str) -> DecodeError {
+    DecodeError::InvalidSegments {
+        reason: reason.to_string(),
+    }
+}
+
+fn read_u32_at<R: Read + Seek>(reader: &mut R, position: u64) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.seek(SeekFrom::Start(position))?;
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::files::extractor::ZipExtractor;
+    use std::fs;
+    use std::io::{Cursor, Write};
+    use tempfile::tempdir;
+    use zip::CompressionMethod;
+    use zip::write::{FileOptions, ZipWriter};
+
+    /// 按 `cuts`（相对于加上分卷签名后的数据）把普通归档改写为分卷归档
+    fn split_zip(bytes: &[u8], cuts: &[usize]) -> Vec<Vec<u8>> {
+        let mut data = SPLIT_SIGNATURE.to_le_bytes().to_vec();
+        data.extend_from_slice(bytes);
+        let starts: Vec<usize> = std::iter::once(0).chain(cuts.iter().copied()).collect();
+        let disk_of = |pos: usize| starts.iter().rposition(|&start| start <= pos).unwrap();
+
+        let eocd = EndOfCentralDirectory::find(&mut Cursor::new(bytes))
+            .unwrap()
+            .unwrap();
+        let central = eocd.central_directory_offset as usize + 4;
+        let mut at = central;
+        for _ in 0..eocd.entries {
+            let local = read_u32(&data, at + 42) as usize + 4;
+            let disk = disk_of(local);
+            data[at + 34..at + 36].copy_from_slice(&(disk as u16).to_le_bytes());
+            data[at + 42..at + 46].copy_from_slice(&((local - starts[disk]) as u32).to_le_bytes());
+            at += CENTRAL_HEADER_LEN
+                + read_u16(&data, at + 28) as usize
+                + read_u16(&data, at + 30) as usize
+                + read_u16(&data, at + 32) as usize;
+        }
+        let record = eocd.position as usize + 4;
+        let central_disk = disk_of(central);
+        data[record + 4..record + 6].copy_from_slice(&(cuts.len() as u16).to_le_bytes());
+        data[record + 6..record + 8].copy_from_slice(&(central_disk as u16).to_le_bytes());
+        data[record + 16..record + 20]
+            .copy_from_slice(&((central - starts[central_disk]) as u32).to_le_bytes());
+
+        let mut ends = cuts.to_vec();
+        ends.push(data.len());
+        starts
+            .iter()
+            .zip(ends)
+            .map(|(&start, end)| data[start..end].to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn test_segmented_source() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        let contents: Vec<(String, Vec<u8>)> = (0..4)
+            .map(|i| (format!("dir/{}.bin", i), vec![b'a' + i as u8; 3000]))
+            .collect();
+        for (name, data) in &contents {
+            writer.start_file(name.as_str(), stored).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        // 切点落在条目数据中间，条目跨越分卷
+        let dir = tempdir().unwrap();
+        let mut paths = Vec::new();
+        let segments = split_zip(&bytes, &[2000, 5000, 9000]);
+        for (i, segment) in segments.iter().enumerate() {
+            let name = if i + 1 == segments.len() {
+                "archive.zip".to_string()
+            } else {
+                format!("archive.z{:02}", i + 1)
+            };
+            let path = dir.path().join(name);
+            fs::write(&path, segment).unwrap();
+            paths.push(path);
+        }
+
+        let out = dir.path().join("out");
+        let report = ZipExtractor::from_segments(&paths, &out)
+            .unwrap()
+            .worker_threads(2)
+            .extract()
+            .unwrap();
+        assert_eq!(report.files_extracted, 4);
+        for (name, data) in &contents {
+            assert_eq!(&fs::read(out.join(name)).unwrap(), data);
+        }
+
+        // 缺少分卷
+        let err = SegmentedSource::open(&paths[1..]).unwrap_err();
+        assert!(matches!(err, DecodeError::InvalidSegments { .. }));
+        // 分卷顺序错误
+        let mut swapped = paths.clone();
+        swapped.swap(1, 2);
+        let err = SegmentedSource::open(&swapped).unwrap_err();
+        assert!(
+            matches!(err, DecodeError::InvalidSegments { reason } if reason.contains("本地文件头"))
+        );
+        assert!(matches!(
+            SegmentedSource::open(&[]),
+            Err(DecodeError::InvalidSegments { .. })
+        ));
+    }
+}
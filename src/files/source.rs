@@ -0,0 +1,195 @@
+use memmap2::Mmap;
+use std::{
+    fs::File,
+    io::{self, BufReader, Cursor, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// 可读且可定位的归档句柄
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// ZIP 归档的数据来源
+///
+/// 每次调用 [`ArchiveSource::open`] 都必须返回一个独立的句柄，
+/// 并行解压时每个工作线程各自持有一个，互不加锁。
+pub trait ArchiveSource: Send + Sync {
+    /// 打开一个新的独立句柄
+    fn open(&self) -> io::Result<Box<dyn ReadSeek>>;
+}
+
+/// 任意返回 `Read + Seek` 句柄的闭包都可以作为数据源
+impl<F, R> ArchiveSource for F
+where
+    F: Fn() -> io::Result<R> + Send + Sync,
+    R: Read + Seek + Send + 'static,
+{
+    fn open(&self) -> io::Result<Box<dyn ReadSeek>> {
+        Ok(Box::new(self()?))
+    }
+}
+
+/// 默认的文件数据源：每个句柄单独打开文件并套上读缓冲
+#[derive(Debug, Clone)]
+pub struct FileSource {
+    path: PathBuf,
+    buffer_size: usize,
+}
+
+impl FileSource {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            buffer_size: 2 * 1024 * 1024, // 默认 2MB 读缓冲
+        }
+    }
+
+    /// 设置每个句柄的读缓冲区大小 (字节)
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl ArchiveSource for FileSource {
+    fn open(&self) -> io::Result<Box<dyn ReadSeek>> {
+        let file = File::open(&self.path)?;
+        Ok(Box::new(BufReader::with_capacity(self.buffer_size, file)))
+    }
+}
+
+/// 内存映射数据源：所有句柄共享同一份映射，各自持有独立的 `Cursor`
+///
+/// 高并行度下不会为每个工作线程占用一个文件描述符，随机访问也更快。
+#[derive(Debug, Clone)]
+pub struct MmapSource {
+    map: Arc<Mmap>,
+}
+
+impl MmapSource {
+    /// 映射整个文件
+    pub fn open_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: 映射只读；解压期间归档文件被其他进程截断或改写属于调用方的使用错误
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self { map: Arc::new(map) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl ArchiveSource for MmapSource {
+    fn open(&self) -> io::Result<Box<dyn ReadSeek>> {
+        Ok(Box::new(Cursor::new(SharedMap(Arc::clone(&self.map)))))
+    }
+}
+
+/// 让 `Cursor` 直接读取共享映射
+struct SharedMap(Arc<Mmap>);
+
+impl AsRef<[u8]> for SharedMap {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// 把单个 `Read + Seek` 句柄中从 `offset` 开始的部分作为数据源
+///
+/// 所有句柄共享同一个底层读取器（读取时加锁并定位），各自维护独立的位置，
+/// 因此同样支持并行解压；句柄看到的偏移从 `offset` 开始计为 0。
+#[derive(Debug)]
+pub struct ReaderSource<R> {
+    inner: Arc<Mutex<R>>,
+    offset: u64,
+    len: u64,
+}
+
+impl<R: Read + Seek> ReaderSource<R> {
+    pub fn new(mut reader: R, offset: u64) -> io::Result<Self> {
+        let end = reader.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            inner: Arc::new(Mutex::new(reader)),
+            offset,
+            len: end.saturating_sub(offset),
+        })
+    }
+
+    /// 读取窗口开头的字节（不足时用 0 补齐）
+    pub(crate) fn peek<const N: usize>(&self) -> io::Result<[u8; N]> {
+        let mut bytes = [0u8; N];
+        let mut handle = self.handle();
+        let mut filled = 0;
+        while filled < N {
+            match handle.read(&mut bytes[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn handle(&self) -> ReaderHandle<R> {
+        ReaderHandle {
+            inner: Arc::clone(&self.inner),
+            offset: self.offset,
+            len: self.len,
+            pos: 0,
+        }
+    }
+}
+
+impl<R: Read + Seek + Send + 'static> ArchiveSource for ReaderSource<R> {
+    fn open(&self) -> io::Result<Box<dyn ReadSeek>> {
+        Ok(Box::new(BufReader::with_capacity(64 * 1024, self.handle())))
+    }
+}
+
+/// [`ReaderSource`] 的单个句柄
+struct ReaderHandle<R> {
+    inner: Arc<Mutex<R>>,
+    offset: u64,
+    len: u64,
+    pos: u64,
+}
+
+impl<R: Read + Seek> Read for ReaderHandle<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        if remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let max = buf
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        inner.seek(SeekFrom::Start(self.offset + self.pos))?;
+        let n = inner.read(&mut buf[..max])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R> Seek for ReaderHandle<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+        self.pos = target
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "定位到了数据源开头之前"))?;
+        Ok(self.pos)
+    }
+}
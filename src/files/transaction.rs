@@ -0,0 +1,149 @@
+use super::{
+    HandleAction, HandledFile, RenameOptions, RenameRules, Result, check_extension, handles_for,
+    rename_detailed, rule_target,
+};
+use crate::FileOpError;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// 可撤销的批量重命名
+///
+/// 由 [`begin`](Self::begin) 按 [`get_file_handles`](super::get_file_handles) 的规则重命名并打开文件，
+/// 并记录每一次重命名。调用 [`commit`](Self::commit) 后保留结果；未提交就被丢弃
+/// （包括后续处理中途返回错误或 panic）时按相反顺序把文件改回原名。
+///
+/// 重命名覆盖了已存在的目标文件时，被覆盖的内容无法恢复。
+#[derive(Debug)]
+pub struct RenameTransaction {
+    files: Vec<HandledFile>,
+    committed: bool,
+}
+
+impl RenameTransaction {
+    /// 对单个文件或目录中的文件（不递归）执行重命名，任何一个文件失败时撤销已完成的部分并返回错误
+    pub fn begin<P: AsRef<Path>>(path: P, rename_hash: &HashMap<String, String>) -> Result<Self> {
+        let rules = RenameRules::from_map(rename_hash);
+        let mut transaction = RenameTransaction {
+            files: Vec::new(),
+            committed: false,
+        };
+        handles_for(path.as_ref(), |file| {
+            check_extension(file, &rules)?;
+            let handled = rename_detailed(
+                file,
+                &|path: &Path| rule_target(path, &rules),
+                &RenameOptions::default(),
+            )?;
+            transaction.files.push(handled);
+            Ok(())
+        })?;
+        Ok(transaction)
+    }
+
+    /// 已处理的文件，其中的句柄指向重命名后的文件
+    pub fn files(&self) -> &[HandledFile] {
+        &self.files
+    }
+
+    /// 保留所有重命名，返回打开的文件
+    pub fn commit(mut self) -> Vec<HandledFile> {
+        self.committed = true;
+        std::mem::take(&mut self.files)
+    }
+
+    /// 立即撤销所有重命名
+    ///
+    /// 某个文件无法改回时继续处理其余文件，最后返回第一个错误。
+    pub fn rollback(mut self) -> Result<()> {
+        self.committed = true;
+        self.undo()
+    }
+
+    /// 先关闭句柄，再按相反顺序把文件改回原名
+    fn undo(&mut self) -> Result<()> {
+        let mut first_error = None;
+        for file in self.files.drain(..).rev() {
+            let HandleAction::Renamed(original) = file.action else {
+                continue;
+            };
+            drop(file.handle);
+            if let Err(source) = fs::rename(&file.path, &original) {
+                let e = FileOpError::RenameFailed {
+                    from: file.path,
+                    to: original,
+                    source,
+                };
+                log::warn!("撤销重命名失败: {}", e);
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+impl Drop for RenameTransaction {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = self.undo();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn rules() -> HashMap<String, String> {
+        HashMap::from([("txt".to_string(), "srt".to_string())])
+    }
+
+    #[test]
+    fn test_rename_transaction() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("a.txt"), "a")?;
+        fs::write(dir.path().join("b.txt"), "b")?;
+        fs::write(dir.path().join("c.md"), "c")?;
+
+        // 未提交时丢弃即恢复原名
+        {
+            let transaction = RenameTransaction::begin(dir.path(), &rules())?;
+            assert_eq!(transaction.files().len(), 3);
+            assert!(dir.path().join("a.srt").exists());
+            assert!(!dir.path().join("a.txt").exists());
+        }
+        assert!(dir.path().join("a.txt").exists());
+        assert!(dir.path().join("b.txt").exists());
+        assert!(!dir.path().join("a.srt").exists());
+
+        RenameTransaction::begin(dir.path(), &rules())?.rollback()?;
+        assert!(dir.path().join("b.txt").exists());
+
+        let files = RenameTransaction::begin(dir.path(), &rules())?.commit();
+        assert_eq!(files.len(), 3);
+        assert!(dir.path().join("a.srt").exists());
+        assert!(dir.path().join("b.srt").exists());
+        assert!(dir.path().join("c.md").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_transaction_failure_rolls_back() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("a.txt"), "a")?;
+        fs::write(dir.path().join("b.txt"), "b")?;
+        // 没有扩展名的文件在按规则处理时报错
+        fs::write(dir.path().join("noext"), "x")?;
+
+        assert!(matches!(
+            RenameTransaction::begin(dir.path(), &rules()),
+            Err(FileOpError::NoExtension(path)) if path == dir.path().join("noext")
+        ));
+        assert!(dir.path().join("a.txt").exists());
+        assert!(dir.path().join("b.txt").exists());
+        assert!(!dir.path().join("a.srt").exists());
+        assert!(!dir.path().join("b.srt").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+pub mod decode;
+pub mod error;
+pub mod files;
+pub mod ui;
+
+pub use error::{DecodeError, FileOpError, UnsafePathError};
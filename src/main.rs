@@ -1,4 +1 @@
-mod files;
-mod ui;
-
 fn main() {}